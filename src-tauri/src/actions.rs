@@ -0,0 +1,396 @@
+use crate::app_state::SharedAppState;
+use crate::clipboard_history;
+use crate::funcs;
+use crate::hotspots;
+use crate::input_mapper::{self, OskMode};
+use crate::launcher;
+use crate::layout;
+use crate::monitor;
+use crate::staging::{self, StagingAction};
+use crate::text_delivery::{self, TextMethod};
+use crate::window_manager::{self, WindowOp};
+use crate::window_switcher;
+use enigo::{Button as MouseButton, Direction, Enigo, Key, Keyboard, Mouse};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Something a gamepad button can be bound to. Held actions (mouse buttons,
+// keys) follow the button; one-shot actions fire on press.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Mouse {
+        button: String,
+    },
+    Key {
+        key: String,
+        #[serde(default)]
+        modifiers: Vec<String>,
+    },
+    SendText {
+        text: String,
+        #[serde(default)]
+        method: TextMethod,
+    },
+    OpenOsk,
+    // OSK key by its simple-keyboard name, e.g. "{bksp}", through the same path as a
+    // key pressed on the OSK itself
+    OskKey {
+        key: String,
+    },
+    OskShift,
+    CycleLayout {
+        step: i32,
+    },
+    // Recent clips over the OSK, picked with the stick
+    ClipboardHistory,
+    // Held: opens one of the profile's radial menus, fires the picked slot on release
+    Radial {
+        menu: String,
+    },
+    // Each action pressed and released in turn
+    Macro {
+        actions: Vec<Action>,
+        #[serde(default)]
+        delay_ms: u64,
+    },
+    // Switches the OSK mode and opens the OSK in it
+    SetOskMode {
+        mode: OskMode,
+    },
+    // Pick a window from a list and bring it forward, instead of holding Alt+Tab
+    WindowSwitcher,
+    // Acts on the typing target, or the foreground window in system mode
+    Window {
+        op: WindowOp,
+    },
+    // Starts a program directly, without a shell
+    Launch {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        cwd: Option<String>,
+        // Bring an open window of the program forward instead of starting another
+        #[serde(default)]
+        focus_existing: bool,
+        // Ask for a second press first. Destructive commands always ask.
+        #[serde(default)]
+        confirm: bool,
+    },
+    // Runs a line through `cmd /C` or `sh -c`
+    RunCommand {
+        command: String,
+        #[serde(default)]
+        cwd: Option<String>,
+        #[serde(default)]
+        confirm: bool,
+    },
+    // Toggles the media layer: playback buttons, analog volume
+    MediaLayer,
+    // Narrow a 3x3 grid over the monitor down to where the cursor should go
+    GridJump,
+    // Cursor positions by name. Besides saved slots, "window", "last_click" and
+    // "monitor:<n>" are always there to jump to.
+    SaveHotspot {
+        slot: String,
+    },
+    JumpHotspot {
+        slot: String,
+    },
+    CycleHotspots {
+        step: i32,
+    },
+    // Cursor to the middle of the next monitor
+    NextMonitor,
+    // Edits or commits the OSK staging buffer
+    Staging {
+        action: StagingAction,
+    },
+}
+
+pub fn run(action: &Action, direction: Direction, app: &AppHandle, enigo: &mut Enigo) {
+    let pressed = direction == Direction::Press;
+
+    match action {
+        Action::Mouse { button } => {
+            if let Some(button) = parse_mouse_button(button) {
+                if pressed {
+                    hotspots::record_click(app, enigo);
+                }
+                let _ = enigo.button(button, direction);
+            }
+        }
+        Action::Key { key, modifiers } => key_combo(enigo, key, modifiers, direction),
+        Action::SendText { text, method } => {
+            if pressed {
+                text_delivery::deliver_in_background(app, text.clone(), *method);
+            }
+        }
+        Action::OpenOsk => {
+            if pressed {
+                funcs::open_osk(app);
+            }
+        }
+        Action::OskKey { key } => text_delivery::press_osk_key(app, key, if pressed { "down" } else { "up" }),
+        Action::OskShift => {
+            if pressed {
+                let _ = app.emit("osk:nav:shift", ());
+            }
+        }
+        Action::CycleLayout { step } => {
+            if pressed {
+                layout::cycle(app, *step);
+            }
+        }
+        Action::ClipboardHistory => {
+            if pressed {
+                clipboard_history::open_panel(app);
+            }
+        }
+        Action::Radial { menu } => {
+            if pressed {
+                input_mapper::open_radial(app, menu);
+            } else {
+                input_mapper::release_radial(app, enigo);
+            }
+        }
+        Action::Macro { actions, delay_ms } => {
+            if pressed {
+                for (i, action) in actions.iter().enumerate() {
+                    if i > 0 && *delay_ms > 0 {
+                        thread::sleep(Duration::from_millis(*delay_ms));
+                    }
+                    run(action, Direction::Press, app, enigo);
+                    run(action, Direction::Release, app, enigo);
+                }
+            }
+        }
+        Action::SetOskMode { mode } => {
+            if pressed {
+                let (profiles, osk_open) = {
+                    let state_handle = app.state::<SharedAppState>();
+                    let mut state = state_handle.lock().unwrap();
+                    state.profiles.active_mut().osk_mode = *mode;
+                    (state.profiles.clone(), state.osk_open)
+                };
+                profiles.save(app);
+                if !osk_open {
+                    funcs::open_osk(app);
+                }
+            }
+        }
+        Action::WindowSwitcher => {
+            if pressed {
+                window_switcher::open(app);
+            }
+        }
+        Action::Window { op } => window_manager::run(app, *op, pressed),
+        Action::Launch {
+            program,
+            args,
+            cwd,
+            focus_existing,
+            confirm,
+        } => {
+            if pressed {
+                launcher::launch(app, program, args, cwd.as_deref(), *focus_existing, *confirm);
+            }
+        }
+        Action::RunCommand { command, cwd, confirm } => {
+            if pressed {
+                launcher::run_command(app, command, cwd.as_deref(), *confirm);
+            }
+        }
+        Action::MediaLayer => {
+            if pressed {
+                input_mapper::toggle_media_layer(app);
+            }
+        }
+        Action::GridJump => {
+            if pressed {
+                input_mapper::open_grid_jump(app, enigo);
+            }
+        }
+        Action::SaveHotspot { slot } => {
+            if pressed {
+                hotspots::save(app, enigo, slot);
+            }
+        }
+        Action::JumpHotspot { slot } => {
+            if pressed {
+                hotspots::jump(app, enigo, slot);
+            }
+        }
+        Action::CycleHotspots { step } => {
+            if pressed {
+                hotspots::cycle(app, enigo, *step);
+            }
+        }
+        Action::NextMonitor => {
+            if pressed {
+                monitor::jump_to_next(app, enigo);
+            }
+        }
+        Action::Staging { action } => {
+            if pressed {
+                staging::run(app, *action);
+            }
+        }
+    }
+}
+
+// Modifiers go down before the key and come up after it
+pub fn key_combo(enigo: &mut Enigo, key: &str, modifiers: &[String], direction: Direction) {
+    let Some(key) = parse_key(key) else {
+        return;
+    };
+    let modifiers: Vec<Key> = modifiers.iter().filter_map(|m| parse_key(m)).collect();
+
+    if direction != Direction::Release {
+        for m in &modifiers {
+            let _ = enigo.key(*m, Direction::Press);
+        }
+    }
+    let _ = enigo.key(key, direction);
+    if direction != Direction::Press {
+        for m in modifiers.iter().rev() {
+            let _ = enigo.key(*m, Direction::Release);
+        }
+    }
+}
+
+pub fn parse_mouse_button(name: &str) -> Option<MouseButton> {
+    match name {
+        "left" => Some(MouseButton::Left),
+        "right" => Some(MouseButton::Right),
+        "middle" => Some(MouseButton::Middle),
+        _ => None,
+    }
+}
+
+pub fn parse_key(name: &str) -> Option<Key> {
+    let key = match name.to_lowercase().as_str() {
+        "shift" => Key::Shift,
+        "ctrl" | "control" => Key::Control,
+        "alt" => Key::Alt,
+        "win" | "meta" | "super" => Key::Meta,
+        "enter" | "return" => Key::Return,
+        "backspace" => Key::Backspace,
+        "tab" => Key::Tab,
+        "esc" | "escape" => Key::Escape,
+        "space" => Key::Space,
+        "delete" => Key::Delete,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "capslock" => Key::CapsLock,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        "playpause" => Key::MediaPlayPause,
+        #[cfg(not(target_os = "macos"))]
+        "mediastop" => Key::MediaStop,
+        "nexttrack" => Key::MediaNextTrack,
+        "prevtrack" => Key::MediaPrevTrack,
+        "mute" => Key::VolumeMute,
+        "volumedown" => Key::VolumeDown,
+        "volumeup" => Key::VolumeUp,
+        _ => {
+            // Single characters are typed as-is, e.g. "c" for Ctrl+C
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::Unicode(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+fn key(key: &str) -> Action {
+    Action::Key {
+        key: key.to_string(),
+        modifiers: Vec::new(),
+    }
+}
+
+fn mouse(button: &str) -> Action {
+    Action::Mouse {
+        button: button.to_string(),
+    }
+}
+
+// Default system mode bindings for new profiles
+pub fn default_system_buttons() -> BTreeMap<String, Action> {
+    BTreeMap::from([
+        ("start".to_string(), Action::OpenOsk),
+        ("south".to_string(), mouse("left")),
+        ("east".to_string(), key("backspace")),
+        ("west".to_string(), mouse("right")),
+        ("north".to_string(), key("enter")),
+        ("lb".to_string(), key("left")),
+        ("rb".to_string(), key("right")),
+        ("dpad_up".to_string(), key("up")),
+        ("dpad_down".to_string(), key("down")),
+        ("dpad_left".to_string(), key("left")),
+        ("dpad_right".to_string(), key("right")),
+        ("lt".to_string(), Action::Radial { menu: "main".to_string() }),
+        ("rt".to_string(), Action::WindowSwitcher),
+        ("ls".to_string(), Action::Radial { menu: "windows".to_string() }),
+        ("rs".to_string(), Action::MediaLayer),
+    ])
+}
+
+// Media layer bindings; the triggers and right stick are volume on top of these
+pub fn default_media_buttons() -> BTreeMap<String, Action> {
+    BTreeMap::from([
+        ("south".to_string(), key("playpause")),
+        ("north".to_string(), key("mute")),
+        ("west".to_string(), key("mediastop")),
+        ("lb".to_string(), key("prevtrack")),
+        ("rb".to_string(), key("nexttrack")),
+        ("dpad_left".to_string(), key("prevtrack")),
+        ("dpad_right".to_string(), key("nexttrack")),
+        ("dpad_up".to_string(), key("volumeup")),
+        ("dpad_down".to_string(), key("volumedown")),
+        ("east".to_string(), Action::MediaLayer),
+        ("start".to_string(), Action::MediaLayer),
+        ("rs".to_string(), Action::MediaLayer),
+    ])
+}
+
+fn osk_key(key: &str) -> Action {
+    Action::OskKey { key: key.to_string() }
+}
+
+// OSK (grid) mode bindings, on top of the built-in South/East/Select/D-pad navigation.
+// Layout cycling has no default button (the sticks' clicks hold the caret modifiers);
+// bind `CycleLayout` here to get it.
+pub fn default_osk_buttons() -> BTreeMap<String, Action> {
+    BTreeMap::from([
+        ("west".to_string(), osk_key("{bksp}")),
+        ("north".to_string(), osk_key("{space}")),
+        ("lb".to_string(), osk_key("{arrowleft}")),
+        ("rb".to_string(), osk_key("{arrowright}")),
+        ("lt".to_string(), Action::OskShift),
+        ("rt".to_string(), osk_key("{enter}")),
+    ])
+}
@@ -0,0 +1,198 @@
+use crate::app_state::SharedAppState;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+// Lowercase letter -> variants, most used first for the language
+const FRENCH: &[(char, &[&str])] = &[
+    ('e', &["é", "è", "ê", "ë"]),
+    ('a', &["à", "â", "æ"]),
+    ('c', &["ç"]),
+    ('i', &["î", "ï"]),
+    ('o', &["ô", "œ"]),
+    ('u', &["ù", "û", "ü"]),
+    ('y', &["ÿ"]),
+];
+const GERMAN: &[(char, &[&str])] = &[
+    ('a', &["ä"]),
+    ('o', &["ö"]),
+    ('u', &["ü"]),
+    ('s', &["ß"]),
+    ('e', &["é"]),
+];
+// Everything else: the common Latin accents
+const GENERIC: &[(char, &[&str])] = &[
+    ('a', &["á", "à", "â", "ä", "ã", "å", "ā", "æ"]),
+    ('c', &["ç", "ć", "č"]),
+    ('e', &["é", "è", "ê", "ë", "ē"]),
+    ('i', &["í", "ì", "î", "ï", "ī"]),
+    ('n', &["ñ", "ń"]),
+    ('o', &["ó", "ò", "ô", "ö", "õ", "ø", "ō", "œ"]),
+    ('s', &["ß", "ś", "š"]),
+    ('u', &["ú", "ù", "û", "ü", "ū"]),
+    ('y', &["ý", "ÿ"]),
+    ('z', &["ž", "ź", "ż"]),
+];
+
+// A key held down through `send_key` that has variants to offer
+#[derive(Serialize, Clone, Debug)]
+pub struct LongPress {
+    pub base: String,
+    pub alternates: Vec<String>,
+    pub selected: usize,
+    #[serde(skip)]
+    since: Instant,
+    #[serde(skip)]
+    shown: bool,
+}
+
+pub enum Press {
+    // Not ours, send as usual
+    Pass,
+    // Swallowed while we wait to see if it becomes a long press
+    Hold,
+    // Released before the popup: type the base character normally
+    Type(String),
+    // Released with the popup open: type the picked variant as unicode
+    Commit(String),
+}
+
+fn table(language: Option<&str>) -> &'static [(char, &'static [&'static str])] {
+    match language {
+        Some("fr") => FRENCH,
+        Some("de") => GERMAN,
+        _ => GENERIC,
+    }
+}
+
+fn alternates_for(language: Option<&str>, base: &str) -> Vec<String> {
+    let mut chars = base.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return Vec::new();
+    };
+    let upper = c.is_uppercase();
+    let Some(lower) = c.to_lowercase().next() else {
+        return Vec::new();
+    };
+
+    let Some((_, variants)) = table(language).iter().find(|(k, _)| *k == lower) else {
+        return Vec::new();
+    };
+    variants
+        .iter()
+        .filter_map(|v| {
+            if !upper {
+                return Some(v.to_string());
+            }
+            // ß has no single-character uppercase
+            let u = v.to_uppercase();
+            (u.chars().count() == 1).then_some(u)
+        })
+        .collect()
+}
+
+// Runs in front of `send_key`. Letters with variants are typed on release instead of
+// press, so holding them can open the popup.
+pub fn intercept(app: &AppHandle, phase: &str, text: Option<&str>) -> Press {
+    let state_handle = app.state::<SharedAppState>();
+    let mut state = state_handle.lock().unwrap();
+
+    match phase {
+        "down" => {
+            let Some(text) = text else {
+                return Press::Pass;
+            };
+            let language = state
+                .layouts
+                .get_or_default(&state.profiles.active().layout)
+                .and_then(|l| l.language.clone());
+            let alternates = alternates_for(language.as_deref(), text);
+            if alternates.is_empty() {
+                return Press::Pass;
+            }
+            state.long_press = Some(LongPress {
+                base: text.to_string(),
+                alternates,
+                selected: 0,
+                since: Instant::now(),
+                shown: false,
+            });
+            Press::Hold
+        }
+        // No auto-repeat for keys with a popup
+        "repeat" if state.long_press.is_some() => Press::Hold,
+        "up" => match state.long_press.take() {
+            Some(press) if press.shown => {
+                drop(state);
+                let _ = app.emit("osk:alternates", None::<LongPress>);
+                Press::Commit(press.alternates[press.selected].clone())
+            }
+            Some(press) => Press::Type(press.base),
+            None => Press::Pass,
+        },
+        _ => Press::Pass,
+    }
+}
+
+// Called every OSK tick, opens the popup once the key has been held long enough
+pub fn tick(app: &AppHandle) {
+    let press = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        match state.long_press.as_mut() {
+            Some(press) if !press.shown && press.since.elapsed() >= LONG_PRESS => {
+                press.shown = true;
+                press.clone()
+            }
+            _ => return,
+        }
+    };
+    let _ = app.emit("osk:alternates", Some(press));
+}
+
+pub fn is_open(app: &AppHandle) -> bool {
+    let state_handle = app.state::<SharedAppState>();
+    let state = state_handle.lock().unwrap();
+    state.long_press.as_ref().is_some_and(|p| p.shown)
+}
+
+// Moves the highlighted variant, clamped to the ends of the row
+pub fn step(app: &AppHandle, dx: i32) {
+    let press = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let Some(press) = state.long_press.as_mut().filter(|p| p.shown) else {
+            return;
+        };
+        let last = press.alternates.len() as i32 - 1;
+        press.selected = (press.selected as i32 + dx).clamp(0, last) as usize;
+        press.clone()
+    };
+    let _ = app.emit("osk:alternates", Some(press));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants_follow_the_layout_language() {
+        assert_eq!(alternates_for(Some("de"), "a"), ["ä"]);
+        assert_eq!(alternates_for(Some("fr"), "c"), ["ç"]);
+        assert_eq!(alternates_for(None, "n"), ["ñ", "ń"]);
+    }
+
+    #[test]
+    fn capitals_get_capital_variants() {
+        assert_eq!(alternates_for(Some("fr"), "E"), ["É", "È", "Ê", "Ë"]);
+        assert!(alternates_for(Some("de"), "S").is_empty());
+    }
+
+    #[test]
+    fn only_single_letters_have_variants() {
+        assert!(alternates_for(None, ".com").is_empty());
+        assert!(alternates_for(None, "q").is_empty());
+    }
+}
@@ -1,24 +1,46 @@
+use crate::alternates::LongPress;
+use crate::clipboard_history::ClipboardHistory;
+use crate::compose::ComposeState;
+use crate::hotspots::HotspotState;
+use crate::ime::ImeState;
+use crate::input_mapper::{GridJump, KeyGeometry, RadialMenu};
+use crate::launcher::PendingConfirm;
+use crate::layout::LayoutRegistry;
+use crate::prediction::Predictor;
+use crate::profile::ProfileStore;
+use crate::staging::StagingBuffer;
+use crate::window_manager::WindowManager;
+use crate::window_switcher::Switcher;
 use std::sync::Mutex;
 use std::time::Instant;
 
+#[derive(Default)]
 pub struct AppState {
     pub active: bool,
     pub osk_open: bool,
     pub toggle_guard: bool,
     pub last_toggle_time: Option<Instant>,
     pub target_hwnd: isize,
-}
-
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            active: false,
-            osk_open: false,
-            toggle_guard: false,
-            last_toggle_time: None,
-            target_hwnd: 0,
-        }
-    }
+    pub profiles: ProfileStore,
+    pub predictor: Predictor,
+    pub layouts: LayoutRegistry,
+    // Key held on the OSK that may turn into the accent popup
+    pub long_press: Option<LongPress>,
+    pub compose: ComposeState,
+    pub ime: ImeState,
+    pub staging: StagingBuffer,
+    pub clipboard: ClipboardHistory,
+    // Radial menu held open by its button
+    pub radial: Option<RadialMenu>,
+    pub switcher: Option<Switcher>,
+    pub window_manager: WindowManager,
+    pub launch_confirm: Option<PendingConfirm>,
+    // Media layer on; whether it had to show the OSK window for its OSD
+    pub media_layer: Option<bool>,
+    pub grid_jump: Option<GridJump>,
+    pub hotspots: HotspotState,
+    // Key centres reported by the frontend for the current layout
+    pub osk_geometry: Vec<KeyGeometry>,
 }
 
 pub type SharedAppState = Mutex<AppState>;
@@ -0,0 +1,315 @@
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use crate::native_window;
+use crate::text_delivery::{self, TextMethod};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+const HISTORY_FILE: &str = "clipboard_history.json";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Clip {
+    pub id: u64,
+    pub text: String,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+struct Panel {
+    selected: usize,
+    // The panel was opened from system mode and closes the OSK again when done
+    opened_osk: bool,
+}
+
+#[derive(Default)]
+pub struct ClipboardHistory {
+    // Newest first
+    clips: Vec<Clip>,
+    next_id: u64,
+    last_seen: Option<String>,
+    // Our own paste path is borrowing the clipboard
+    paused: bool,
+    panel: Option<Panel>,
+}
+
+impl ClipboardHistory {
+    // Nothing is read back unless the profile keeps history across runs
+    pub fn load(&mut self, app: &AppHandle, persist: bool) {
+        if !persist {
+            return;
+        }
+        let Some(Ok(json)) = history_path(app).map(fs::read_to_string) else {
+            return;
+        };
+        match serde_json::from_str::<Vec<Clip>>(&json) {
+            Ok(clips) => {
+                self.next_id = clips.iter().map(|c| c.id + 1).max().unwrap_or(0);
+                self.clips = clips;
+            }
+            Err(e) => eprintln!("Failed to parse {}: {}", HISTORY_FILE, e),
+        }
+    }
+
+    // Moves an existing copy of `text` to the front, keeping its pin. Unpinned clips
+    // beyond `limit` fall off the end.
+    fn push(&mut self, text: String, limit: usize) {
+        let pinned = match self.clips.iter().position(|c| c.text == text) {
+            Some(index) => self.clips.remove(index).pinned,
+            None => false,
+        };
+        self.clips.insert(0, Clip { id: self.next_id, text, pinned });
+        self.next_id += 1;
+
+        let mut unpinned = 0;
+        self.clips.retain(|c| {
+            if c.pinned {
+                return true;
+            }
+            unpinned += 1;
+            unpinned <= limit
+        });
+    }
+
+    pub fn clips(&self) -> &[Clip] {
+        &self.clips
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Clip> {
+        self.clips.iter().find(|c| c.id == id)
+    }
+
+    pub fn dismiss(&mut self) {
+        self.panel = None;
+    }
+}
+
+// With persistence off, a file left over from when it was on is removed. Called with a
+// copy of the clips, outside the state lock.
+fn save(app: &AppHandle, clips: &[Clip], persist: bool) {
+    let Some(path) = history_path(app) else {
+        return;
+    };
+    if !persist {
+        if path.exists() {
+            let _ = fs::remove_file(&path);
+        }
+        return;
+    }
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    match serde_json::to_string(clips) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                eprintln!("Failed to save {}: {}", HISTORY_FILE, e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize clipboard history: {}", e),
+    }
+}
+
+fn history_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(HISTORY_FILE))
+}
+
+pub fn start_monitor(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        poll(&app);
+    });
+}
+
+// The gamepad loop takes the same lock every tick, so the clipboard, window and file
+// work all happens with it released
+fn poll(app: &AppHandle) {
+    let state_handle = app.state::<SharedAppState>();
+    if state_handle.lock().unwrap().clipboard.paused {
+        return;
+    }
+    let Ok(text) = app.clipboard().read_text() else {
+        return;
+    };
+
+    let (exclude, limit, persist) = {
+        let mut state = state_handle.lock().unwrap();
+        if state.clipboard.paused || state.clipboard.last_seen.as_ref() == Some(&text) {
+            return;
+        }
+        state.clipboard.last_seen = Some(text.clone());
+        let profile = state.profiles.active();
+        (profile.clipboard_exclude.clone(), profile.clipboard_history_size, profile.clipboard_persist)
+    };
+    if text.trim().is_empty() || native_window::clipboard_private() {
+        return;
+    }
+
+    // The clipboard owner is the copying app; without one, whoever is in front most likely copied
+    let source = native_window::process_name(native_window::clipboard_owner())
+        .or_else(|| native_window::process_name(native_window::foreground()))
+        .unwrap_or_default()
+        .to_lowercase();
+    let excluded = !source.is_empty() && exclude.iter().any(|name| source.contains(&name.to_lowercase()));
+    if excluded {
+        return;
+    }
+
+    let clips = {
+        let mut state = state_handle.lock().unwrap();
+        state.clipboard.push(text, limit);
+        state.clipboard.clips.clone()
+    };
+    save(app, &clips, persist);
+}
+
+pub fn set_paused(app: &AppHandle, paused: bool) {
+    app.state::<SharedAppState>().lock().unwrap().clipboard.paused = paused;
+}
+
+pub fn update(app: &AppHandle, f: impl FnOnce(&mut Vec<Clip>)) {
+    let (clips, persist) = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        f(&mut state.clipboard.clips);
+        let len = state.clipboard.clips.len();
+        if let Some(panel) = state.clipboard.panel.as_mut() {
+            panel.selected = panel.selected.min(len.saturating_sub(1));
+        }
+        (state.clipboard.clips.clone(), state.profiles.active().clipboard_persist)
+    };
+    save(app, &clips, persist);
+    emit_panel(app);
+}
+
+// Pastes into the typing target through the clipboard, whatever the profile's threshold
+pub fn paste(app: &AppHandle, text: String) {
+    text_delivery::deliver_in_background(app, text, TextMethod::Paste);
+}
+
+pub fn panel_open(app: &AppHandle) -> bool {
+    app.state::<SharedAppState>().lock().unwrap().clipboard.panel.is_some()
+}
+
+// Shows the history over the OSK, opening the OSK first if needed
+pub fn open_panel(app: &AppHandle) {
+    let osk_open = app.state::<SharedAppState>().lock().unwrap().osk_open;
+    if !osk_open {
+        funcs::open_osk(app);
+    }
+    app.state::<SharedAppState>().lock().unwrap().clipboard.panel = Some(Panel { selected: 0, opened_osk: !osk_open });
+    emit_panel(app);
+}
+
+pub fn close_panel(app: &AppHandle) {
+    let panel = app.state::<SharedAppState>().lock().unwrap().clipboard.panel.take();
+    emit_panel(app);
+    if panel.is_some_and(|p| p.opened_osk) {
+        funcs::close_osk(app);
+    }
+}
+
+pub fn step(app: &AppHandle, dy: i32) {
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let last = state.clipboard.clips.len().saturating_sub(1) as i32;
+        let Some(panel) = state.clipboard.panel.as_mut() else {
+            return;
+        };
+        panel.selected = (panel.selected as i32 + dy).clamp(0, last) as usize;
+    }
+    emit_panel(app);
+}
+
+fn selected(app: &AppHandle) -> Option<Clip> {
+    let state_handle = app.state::<SharedAppState>();
+    let state = state_handle.lock().unwrap();
+    let panel = state.clipboard.panel.as_ref()?;
+    state.clipboard.clips.get(panel.selected).cloned()
+}
+
+pub fn paste_selected(app: &AppHandle) {
+    let clip = selected(app);
+    close_panel(app);
+    if let Some(clip) = clip {
+        paste(app, clip.text);
+    }
+}
+
+pub fn toggle_pin_selected(app: &AppHandle) {
+    if let Some(clip) = selected(app) {
+        update(app, |clips| {
+            if let Some(c) = clips.iter_mut().find(|c| c.id == clip.id) {
+                c.pinned = !c.pinned;
+            }
+        });
+    }
+}
+
+pub fn delete_selected(app: &AppHandle) {
+    if let Some(clip) = selected(app) {
+        update(app, |clips| clips.retain(|c| c.id != clip.id));
+    }
+}
+
+// Null when the panel is closed
+pub fn emit_panel(app: &AppHandle) {
+    let payload = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.clipboard.panel.as_ref().map(|panel| {
+            serde_json::json!({
+                "clips": state.clipboard.clips,
+                "selected": panel.selected,
+            })
+        })
+    };
+    let _ = app.emit("osk:clipboard", payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(history: &ClipboardHistory) -> Vec<&str> {
+        history.clips.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn newest_clip_goes_first_with_a_fresh_id() {
+        let mut history = ClipboardHistory::default();
+        history.push("a".to_string(), 10);
+        history.push("b".to_string(), 10);
+        assert_eq!(texts(&history), ["b", "a"]);
+        assert_eq!(history.clips[0].id, 1);
+        assert_eq!(history.clips[1].id, 0);
+    }
+
+    #[test]
+    fn copying_again_moves_the_clip_up_and_keeps_its_pin() {
+        let mut history = ClipboardHistory::default();
+        history.push("a".to_string(), 10);
+        history.push("b".to_string(), 10);
+        history.clips[1].pinned = true;
+        history.push("a".to_string(), 10);
+        assert_eq!(texts(&history), ["a", "b"]);
+        assert!(history.clips[0].pinned);
+        assert_eq!(history.clips[0].id, 2);
+    }
+
+    #[test]
+    fn unpinned_clips_past_the_limit_fall_off() {
+        let mut history = ClipboardHistory::default();
+        history.push("a".to_string(), 2);
+        history.clips[0].pinned = true;
+        for text in ["b", "c", "d"] {
+            history.push(text.to_string(), 2);
+        }
+        assert_eq!(texts(&history), ["d", "c", "a"]);
+    }
+}
@@ -1,5 +1,13 @@
 use crate::app_state::SharedAppState;
-use tauri::Manager;
+use crate::clipboard_history::{self, Clip};
+use crate::input_mapper::{KeyGeometry, OskMode};
+use crate::layout::{self, Layout};
+use crate::prediction;
+use crate::profile::Profile;
+use crate::staging::{self, StagingAction};
+use crate::text_delivery::{self, KeyPayload, TextMethod};
+use crate::tray;
+use crate::window_switcher::{self, WindowInfo};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -7,185 +15,189 @@ pub fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+#[tauri::command]
+pub fn send_key(app_handle: tauri::AppHandle, payload: KeyPayload) {
+    text_delivery::send_key(app_handle, payload);
+}
+
 #[derive(serde::Deserialize)]
-pub struct KeyPayload {
-    phase: String,
-    key: Option<String>,
-    scan_code: Option<u16>,
-    text: Option<String>,
-    modifiers: Option<Vec<String>>,
+pub struct TextPayload {
+    text: String,
+    #[serde(default)]
+    method: TextMethod,
 }
 
 #[tauri::command]
-pub fn send_key(app_handle: tauri::AppHandle, state: tauri::State<SharedAppState>, payload: KeyPayload) {
-    #[cfg(target_os = "windows")]
-    {
-        use windows::Win32::UI::Input::KeyboardAndMouse::{
-            INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_UNICODE,
-            KEYEVENTF_EXTENDEDKEY, SendInput, VIRTUAL_KEY,
-        };
-        use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, SetForegroundWindow};
-        use windows::Win32::Foundation::HWND;
-        use raw_window_handle::HasWindowHandle;
-        use std::mem::size_of;
-
-        // Safety: Check if we are stealing focus and restore target
-        let current_fg = unsafe { GetForegroundWindow() };
-        let target = state.lock().unwrap().target_hwnd;
-        
-        if let Some(window) = app_handle.get_webview_window("main") {
-             if let Ok(handle) = window.window_handle() {
-                 let raw = handle.as_raw();
-                 if let raw_window_handle::RawWindowHandle::Win32(win32_handle) = raw {
-                     let my_hwnd = win32_handle.hwnd.get();
-                     if current_fg.0 as isize == my_hwnd {
-                         // We are focused! Switch back to target.
-                         if target != 0 {
-                             unsafe { let _ = SetForegroundWindow(HWND(target as _)); }
-                             // Small delay to allow focus switch
-                             std::thread::sleep(std::time::Duration::from_millis(10));
-                         }
-                     } else if target != 0 && (current_fg.0 as isize) != target {
-                         // Focus changed to another window. Update target?
-                         // Or just send to whatever is foreground (default behavior of SendInput).
-                         // If we want to stick to target, we should switch.
-                         // But usually user wants to type where they clicked.
-                         // So we update our target to current foreground.
-                         state.lock().unwrap().target_hwnd = current_fg.0 as isize;
-                     }
-                 }
-             }
-        }
+pub fn send_text(app_handle: tauri::AppHandle, payload: TextPayload) {
+    text_delivery::deliver_in_background(&app_handle, payload.text, payload.method);
+}
+
+#[tauri::command]
+pub fn accept_suggestion(app_handle: tauri::AppHandle, state: tauri::State<SharedAppState>, word: String) {
+    let (erase, remaining) = {
+        let mut state = state.lock().unwrap();
+        let replacement = state.predictor.replacement_for(&word);
+        state.predictor.reset();
+        state.predictor.learn(&word);
+        replacement
+    };
+    prediction::emit_suggestions(&app_handle);
 
-        let mut inputs = Vec::new();
-        let mut flags = KEYEVENTF_SCANCODE;
-        if payload.phase == "up" {
-            flags |= KEYEVENTF_KEYUP;
+    // Staged text is fixed up in the buffer rather than in the target
+    if staging::enabled(&app_handle) {
+        for _ in 0..erase {
+            staging::capture_key(&app_handle, "down", "{bksp}");
         }
+        staging::capture_text(&app_handle, &format!("{} ", remaining));
+        return;
+    }
 
-        // Helper to create input for a scan code
-        let create_input = |sc: u16, flags: windows::Win32::UI::Input::KeyboardAndMouse::KEYBD_EVENT_FLAGS| -> INPUT {
-            let mut key_flags = flags;
-            if (sc & 0xFF00) == 0xE000 {
-                key_flags |= KEYEVENTF_EXTENDEDKEY;
-            }
-            INPUT {
-                r#type: INPUT_KEYBOARD,
-                Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
-                    ki: KEYBDINPUT {
-                        wVk: VIRTUAL_KEY(0),
-                        wScan: sc & 0xFF,
-                        dwFlags: key_flags,
-                        time: 0,
-                        dwExtraInfo: 0,
-                    },
-                },
+    std::thread::spawn(move || {
+        let mut enigo = match enigo::Enigo::new(&enigo::Settings::default()) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Failed to init enigo: {:?}", e);
+                return;
             }
         };
-
-        // Handle modifiers
-        if let Some(modifiers) = &payload.modifiers {
-            for modifier in modifiers {
-                let sc = match modifier.as_str() {
-                    "shift" => 0x2A,
-                    "ctrl" => 0x1D,
-                    "alt" => 0x38,
-                    "win" => 0xE05B,
-                    _ => 0,
-                };
-                if sc != 0 {
-                    // If phase is down, press modifier first.
-                    // If phase is up, release modifier last (so we push to inputs later? No, inputs are executed in order).
-                    // Wait, if phase is up, we want KeyUp Key, then KeyUp Modifier.
-                    // If phase is down, we want KeyDown Modifier, then KeyDown Key.
-                    
-                    let mod_flags = if payload.phase == "up" { KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP } else { KEYEVENTF_SCANCODE };
-                    
-                    if payload.phase == "down" {
-                        inputs.push(create_input(sc, mod_flags));
-                    }
-                }
-            }
+        text_delivery::focus_target(&app_handle);
+        for _ in 0..erase {
+            let _ = enigo::Keyboard::key(&mut enigo, enigo::Key::Backspace, enigo::Direction::Click);
         }
+        text_delivery::deliver(&app_handle, &mut enigo, &format!("{} ", remaining), TextMethod::Type);
+    });
+}
 
-        if let Some(sc) = payload.scan_code {
-            inputs.push(create_input(sc, flags));
-        } else if let Some(key_str) = payload.key {
-             let sc = match key_str.as_str() {
-                 "{enter}" => 0x1C,
-                 "{bksp}" => 0x0E,
-                 "{space}" => 0x39,
-                 "{tab}" => 0x0F,
-                 "{esc}" => 0x01,
-                 "{shift}" => 0x2A,
-                 "{lock}" => 0x3A,
-                 "{arrowup}" => 0xE048,
-                 "{arrowdown}" => 0xE050,
-                 "{arrowleft}" => 0xE04B,
-                 "{arrowright}" => 0xE04D,
-                 _ => 0
-             };
-             
-             if sc != 0 {
-                inputs.push(create_input(sc, flags));
-             }
-        } else if let Some(text) = payload.text {
-             if payload.phase == "down" || payload.phase == "repeat" {
-                 for c in text.encode_utf16() {
-                     let input_down = INPUT {
-                        r#type: INPUT_KEYBOARD,
-                        Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
-                            ki: KEYBDINPUT {
-                                wVk: VIRTUAL_KEY(0),
-                                wScan: c,
-                                dwFlags: KEYEVENTF_UNICODE,
-                                time: 0,
-                                dwExtraInfo: 0,
-                            },
-                        },
-                    };
-                    inputs.push(input_down);
-                    
-                    let input_up = INPUT {
-                        r#type: INPUT_KEYBOARD,
-                        Anonymous: windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0 {
-                            ki: KEYBDINPUT {
-                                wVk: VIRTUAL_KEY(0),
-                                wScan: c,
-                                dwFlags: KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
-                                time: 0,
-                                dwExtraInfo: 0,
-                            },
-                        },
-                    };
-                    inputs.push(input_up);
-                 }
-             }
-        }
+#[tauri::command]
+pub fn set_key_geometry(state: tauri::State<SharedAppState>, keys: Vec<KeyGeometry>) {
+    state.lock().unwrap().osk_geometry = keys;
+}
 
-        // Handle modifiers release (if phase is up)
-        if let Some(modifiers) = &payload.modifiers {
-            if payload.phase == "up" {
-                for modifier in modifiers.iter().rev() { // Release in reverse order?
-                    let sc = match modifier.as_str() {
-                        "shift" => 0x2A,
-                        "ctrl" => 0x1D,
-                        "alt" => 0x38,
-                        "win" => 0xE05B,
-                        _ => 0,
-                    };
-                    if sc != 0 {
-                        let mod_flags = KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP;
-                        inputs.push(create_input(sc, mod_flags));
-                    }
-                }
-            }
+#[tauri::command]
+pub fn set_prediction_incognito(state: tauri::State<SharedAppState>, enabled: bool) {
+    state.lock().unwrap().predictor.incognito = enabled;
+}
+
+#[tauri::command]
+pub fn get_profile(state: tauri::State<SharedAppState>) -> Profile {
+    state.lock().unwrap().profiles.active().clone()
+}
+
+#[tauri::command]
+pub fn list_profiles(state: tauri::State<SharedAppState>) -> Vec<String> {
+    state.lock().unwrap().profiles.profiles.iter().map(|p| p.name.clone()).collect()
+}
+
+#[tauri::command]
+pub fn select_profile(app_handle: tauri::AppHandle, state: tauri::State<SharedAppState>, name: String) -> Result<(), String> {
+    let profiles = {
+        let mut state = state.lock().unwrap();
+        if !state.profiles.select(&name) {
+            return Err(format!("Unknown profile: {}", name));
         }
+        state.profiles.clone()
+    };
+    profiles.save(&app_handle);
+    // Each profile remembers its own layout
+    layout::emit_layout(&app_handle);
+    tray::refresh(&app_handle);
+    Ok(())
+}
 
-        if !inputs.is_empty() {
-            unsafe {
-                SendInput(&inputs, size_of::<INPUT>() as i32);
-            }
+#[tauri::command]
+pub fn save_profile(app_handle: tauri::AppHandle, state: tauri::State<SharedAppState>, profile: Profile) {
+    let profiles = {
+        let mut state = state.lock().unwrap();
+        state.profiles.upsert(profile);
+        state.profiles.clone()
+    };
+    profiles.save(&app_handle);
+    tray::refresh(&app_handle);
+}
+
+#[tauri::command]
+pub fn set_osk_mode(app_handle: tauri::AppHandle, state: tauri::State<SharedAppState>, mode: OskMode) {
+    let profiles = {
+        let mut state = state.lock().unwrap();
+        state.profiles.active_mut().osk_mode = mode;
+        state.profiles.clone()
+    };
+    profiles.save(&app_handle);
+}
+
+#[tauri::command]
+pub fn list_layouts(state: tauri::State<SharedAppState>) -> Vec<Layout> {
+    state.lock().unwrap().layouts.all().to_vec()
+}
+
+#[tauri::command]
+pub fn select_layout(app_handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    if layout::select(&app_handle, &name) {
+        Ok(())
+    } else {
+        Err(format!("Unknown layout: {}", name))
+    }
+}
+
+#[tauri::command]
+pub fn set_staging(app_handle: tauri::AppHandle, state: tauri::State<SharedAppState>, enabled: bool) {
+    let profiles = {
+        let mut state = state.lock().unwrap();
+        state.profiles.active_mut().staging = enabled;
+        state.profiles.clone()
+    };
+    profiles.save(&app_handle);
+    staging::emit_state(&app_handle);
+}
+
+#[tauri::command]
+pub fn staging_action(app_handle: tauri::AppHandle, action: StagingAction) {
+    staging::run(&app_handle, action);
+}
+
+#[tauri::command]
+pub fn list_clips(state: tauri::State<SharedAppState>) -> Vec<Clip> {
+    state.lock().unwrap().clipboard.clips().to_vec()
+}
+
+#[tauri::command]
+pub fn paste_clip(app_handle: tauri::AppHandle, state: tauri::State<SharedAppState>, id: u64) -> Result<(), String> {
+    let text = state.lock().unwrap().clipboard.get(id).map(|c| c.text.clone());
+    let text = text.ok_or_else(|| format!("Unknown clip: {}", id))?;
+    clipboard_history::paste(&app_handle, text);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pin_clip(app_handle: tauri::AppHandle, id: u64, pinned: bool) {
+    clipboard_history::update(&app_handle, |clips| {
+        if let Some(clip) = clips.iter_mut().find(|c| c.id == id) {
+            clip.pinned = pinned;
         }
+    });
+}
+
+#[tauri::command]
+pub fn delete_clip(app_handle: tauri::AppHandle, id: u64) {
+    clipboard_history::update(&app_handle, |clips| clips.retain(|c| c.id != id));
+}
+
+// Pinned clips stay
+#[tauri::command]
+pub fn clear_clips(app_handle: tauri::AppHandle) {
+    clipboard_history::update(&app_handle, |clips| clips.retain(|c| c.pinned));
+}
+
+#[tauri::command]
+pub fn list_windows(app_handle: tauri::AppHandle) -> Vec<WindowInfo> {
+    window_switcher::windows(&app_handle)
+}
+
+#[tauri::command]
+pub fn activate_window(app_handle: tauri::AppHandle, handle: isize) -> Result<(), String> {
+    if window_switcher::activate(&app_handle, handle) {
+        Ok(())
+    } else {
+        Err(format!("Could not activate window {}", handle))
     }
 }
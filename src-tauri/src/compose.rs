@@ -0,0 +1,256 @@
+use crate::app_state::SharedAppState;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const COMPOSE_KEY: &str = "{compose}";
+
+// Pressed on the way to a capital letter, so they go through without touching the sequence
+const MODIFIER_KEYS: &[&str] = &["{shift}", "{lock}"];
+
+// Accent mark (as typed after Compose, or as a dead key) -> base/result pairs
+const ACCENTS: &[(&[&str], &str)] = &[
+    (&["'", "´"], "aáeéiíoóuúyýcćnńsśzźAÁEÉIÍOÓUÚYÝCĆNŃSŚZŹ"),
+    (&["`"], "aàeèiìoòuùAÀEÈIÌOÒUÙ"),
+    (&["^"], "aâeêiîoôuûAÂEÊIÎOÔUÛ"),
+    (&["\"", "¨"], "aäeëiïoöuüyÿAÄEËIÏOÖUÜYŸ"),
+    (&["~"], "aãnñoõAÃNÑOÕ"),
+    (&[",", "¸"], "cçCÇ"),
+];
+
+// Compose-only sequences that aren't an accent on a letter
+const SEQUENCES: &[(&str, &str)] = &[
+    ("ae", "æ"),
+    ("AE", "Æ"),
+    ("oe", "œ"),
+    ("OE", "Œ"),
+    ("ss", "ß"),
+    ("oa", "å"),
+    ("OA", "Å"),
+    ("/o", "ø"),
+    ("/O", "Ø"),
+    ("oc", "©"),
+    ("or", "®"),
+    ("tm", "™"),
+    ("<<", "«"),
+    (">>", "»"),
+    ("!!", "¡"),
+    ("??", "¿"),
+    ("=e", "€"),
+    ("L-", "£"),
+    ("Y=", "¥"),
+];
+
+enum Pending {
+    // Compose pressed, characters typed since
+    Compose(String),
+    Dead(String),
+}
+
+#[derive(Default)]
+pub struct ComposeState {
+    pending: Option<Pending>,
+}
+
+pub enum Composed {
+    // Not part of a sequence, send as usual
+    Pass,
+    // Swallowed into the pending sequence
+    Pending,
+    // Sequence finished, type this instead
+    Done(String),
+}
+
+fn accent(mark: &str, c: char) -> Option<char> {
+    let (_, pairs) = ACCENTS.iter().find(|(marks, _)| marks.contains(&mark))?;
+    let pairs: Vec<char> = pairs.chars().collect();
+    pairs.chunks(2).find(|pair| pair[0] == c).map(|pair| pair[1])
+}
+
+// Compose sequences work in either order, like X11: Compose ' e and Compose e '
+fn compose(sequence: &str) -> Option<String> {
+    let chars: Vec<char> = sequence.chars().collect();
+    let [a, b] = chars[..] else {
+        return None;
+    };
+    let sequences: HashMap<&str, &str> = SEQUENCES.iter().copied().collect();
+    accent(&a.to_string(), b)
+        .or_else(|| accent(&b.to_string(), a))
+        .map(String::from)
+        .or_else(|| sequences.get(sequence).map(|s| s.to_string()))
+}
+
+// Runs in `send_key` on the final key. `key` is a simple-keyboard name like "{bksp}",
+// `text` a literal.
+pub fn intercept(app: &AppHandle, phase: &str, key: Option<&str>, text: Option<&str>) -> Composed {
+    let (result, changed) = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let dead_keys = state
+            .layouts
+            .get_or_default(&state.profiles.active().layout)
+            .map(|l| l.dead_keys.clone())
+            .unwrap_or_default();
+        let pending = &mut state.compose.pending;
+        let had_pending = pending.is_some();
+        let result = step(pending, &dead_keys, phase, key, text);
+        (result, phase == "down" && (had_pending || pending.is_some()))
+    };
+    if changed {
+        emit_pending(app);
+    }
+    result
+}
+
+fn step(
+    pending: &mut Option<Pending>,
+    dead_keys: &[String],
+    phase: &str,
+    key: Option<&str>,
+    text: Option<&str>,
+) -> Composed {
+    if key.is_some_and(|k| MODIFIER_KEYS.contains(&k)) {
+        return Composed::Pass;
+    }
+    if phase != "down" {
+        // Repeats and releases of whatever we swallowed
+        return if pending.is_some() || key == Some(COMPOSE_KEY) { Composed::Pending } else { Composed::Pass };
+    }
+
+    match (key, text) {
+        (Some(COMPOSE_KEY), _) => {
+            *pending = Some(Pending::Compose(String::new()));
+            Composed::Pending
+        }
+        // Backspace cancels the sequence, any other special key drops it and goes through
+        (Some("{bksp}"), _) if pending.is_some() => {
+            *pending = None;
+            Composed::Pending
+        }
+        (Some(_), _) => {
+            *pending = None;
+            Composed::Pass
+        }
+        (None, Some(text)) => match pending.take() {
+            None if dead_keys.iter().any(|d| d == text) => {
+                *pending = Some(Pending::Dead(text.to_string()));
+                Composed::Pending
+            }
+            None => Composed::Pass,
+            Some(Pending::Dead(mark)) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    // Dead key then space types the accent itself
+                    (Some(' '), None) => Composed::Done(mark),
+                    (Some(c), None) => match accent(&mark, c) {
+                        Some(accented) => Composed::Done(accented.to_string()),
+                        None => Composed::Done(format!("{}{}", mark, text)),
+                    },
+                    _ => Composed::Done(format!("{}{}", mark, text)),
+                }
+            }
+            Some(Pending::Compose(mut sequence)) => {
+                sequence.push_str(text);
+                if sequence.chars().count() < 2 {
+                    *pending = Some(Pending::Compose(sequence));
+                    Composed::Pending
+                } else {
+                    // Unknown sequences are typed as-is rather than lost
+                    Composed::Done(compose(&sequence).unwrap_or(sequence))
+                }
+            }
+        },
+        (None, None) => Composed::Pass,
+    }
+}
+
+// What is waiting to be combined, for the OSK's pending indicator. Null when idle.
+pub fn emit_pending(app: &AppHandle) {
+    let pending = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        match &state.compose.pending {
+            Some(Pending::Compose(sequence)) => Some(serde_json::json!({ "kind": "compose", "keys": sequence })),
+            Some(Pending::Dead(mark)) => Some(serde_json::json!({ "kind": "dead", "keys": mark })),
+            None => None,
+        }
+    };
+    let _ = app.emit("osk:compose", pending);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accents_keep_the_case() {
+        assert_eq!(accent("'", 'e'), Some('é'));
+        assert_eq!(accent("´", 'E'), Some('É'));
+        assert_eq!(accent("~", 'N'), Some('Ñ'));
+        assert_eq!(accent("^", 'n'), None);
+        assert_eq!(accent("@", 'a'), None);
+    }
+
+    #[test]
+    fn compose_works_in_either_order() {
+        assert_eq!(compose("'e").as_deref(), Some("é"));
+        assert_eq!(compose("e'").as_deref(), Some("é"));
+        assert_eq!(compose(",c").as_deref(), Some("ç"));
+    }
+
+    #[test]
+    fn compose_sequences_are_exact() {
+        assert_eq!(compose("ae").as_deref(), Some("æ"));
+        assert_eq!(compose("=e").as_deref(), Some("€"));
+        assert_eq!(compose("ea"), None);
+        assert_eq!(compose("a"), None);
+        assert_eq!(compose("aee"), None);
+    }
+
+    #[test]
+    fn accent_tables_pair_up() {
+        for (marks, pairs) in ACCENTS {
+            assert_eq!(pairs.chars().count() % 2, 0, "odd table for {:?}", marks);
+        }
+    }
+
+    // Every down/up of a sequence, in order, for `step`
+    fn run(pending: &mut Option<Pending>, dead_keys: &[String], presses: &[(Option<&str>, Option<&str>)]) -> Vec<Composed> {
+        let mut results = Vec::new();
+        for &(key, text) in presses {
+            results.push(step(pending, dead_keys, "down", key, text));
+            step(pending, dead_keys, "up", key, text);
+        }
+        results
+    }
+
+    #[test]
+    fn shift_between_a_dead_key_and_the_letter_keeps_the_accent() {
+        let dead_keys = vec!["'".to_string()];
+        let mut pending = None;
+        let results = run(&mut pending, &dead_keys, &[(None, Some("'")), (Some("{shift}"), None), (None, Some("E"))]);
+        assert!(matches!(results[0], Composed::Pending));
+        assert!(matches!(results[1], Composed::Pass));
+        assert!(matches!(&results[2], Composed::Done(text) if text == "É"));
+        assert!(pending.is_none());
+    }
+
+    #[test]
+    fn shift_inside_a_compose_sequence_passes_through() {
+        let mut pending = None;
+        let results = run(
+            &mut pending,
+            &[],
+            &[(Some(COMPOSE_KEY), None), (None, Some("'")), (Some("{lock}"), None), (None, Some("E"))],
+        );
+        assert!(matches!(results[2], Composed::Pass));
+        assert!(matches!(&results[3], Composed::Done(text) if text == "É"));
+    }
+
+    #[test]
+    fn other_special_keys_drop_the_sequence() {
+        let mut pending = None;
+        let results = run(&mut pending, &[], &[(Some(COMPOSE_KEY), None), (Some("{enter}"), None), (None, Some("e"))]);
+        assert!(matches!(results[1], Composed::Pass));
+        assert!(matches!(results[2], Composed::Pass));
+    }
+}
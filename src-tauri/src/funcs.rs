@@ -1,25 +1,27 @@
 use crate::app_state::SharedAppState;
-use tauri::{AppHandle, Emitter, Manager};
+use crate::layout;
+use crate::native_window;
+use crate::prediction;
+use crate::staging;
+use tauri::{AppHandle, Emitter, LogicalSize, Manager, WebviewWindow};
 
-#[cfg(target_os = "windows")]
-use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+// Full keyboard, and the small footprint used by the multi-tap mode
+const OSK_SIZE: (f64, f64) = (1200.0, 410.0);
+const COMPACT_OSK_SIZE: (f64, f64) = (360.0, 440.0);
 
 pub fn open_osk(app: &AppHandle) {
     let state_handle = app.state::<SharedAppState>();
 
-    #[cfg(target_os = "windows")]
-    {
-        unsafe {
-            let hwnd = GetForegroundWindow();
-            if let Ok(mut state) = state_handle.lock() {
-                state.target_hwnd = hwnd.0 as isize;
-            }
-        }
-    }
+    track_target(app);
 
     if let Ok(mut state) = state_handle.lock() {
         state.osk_open = true;
+        // No idea where the caret is now, start from a fresh word
+        state.predictor.reset();
     }
+    prediction::emit_suggestions(app);
+    layout::emit_layout(app);
+    staging::emit_state(app);
     let _ = app.emit("osk_visibility_changed", true);
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.show();
@@ -29,14 +31,38 @@ pub fn open_osk(app: &AppHandle) {
 }
 
 pub fn close_osk(app: &AppHandle) {
+    staging::commit_on_close(app);
     let state_handle = app.state::<SharedAppState>();
+    let mut target = 0;
     if let Ok(mut state) = state_handle.lock() {
         state.osk_open = false;
+        target = state.target_hwnd;
+        state.long_press = None;
+        state.compose = Default::default();
+        state.ime.cancel();
+        state.clipboard.dismiss();
+        state.predictor.save_user_dictionary(app);
     }
     let _ = app.emit("osk_visibility_changed", false);
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.hide();
     }
+
+    // Hand focus back to the window we were typing into, if it is still around
+    if native_window::exists(target) {
+        native_window::activate(target);
+    }
+}
+
+// Remember the foreground window as the typing target, ignoring the OSK itself
+pub fn track_target(app: &AppHandle) {
+    let fg = native_window::foreground();
+    if fg == 0 || fg == native_window::own_handle(app) {
+        return;
+    }
+    let state_handle = app.state::<SharedAppState>();
+    let mut state = state_handle.lock().unwrap();
+    state.target_hwnd = fg;
 }
 
 pub fn activate(app: &AppHandle) {
@@ -60,3 +86,47 @@ pub fn deactivate(app: &AppHandle) {
 
     close_osk(app)
 }
+
+// Bottom right corner of the monitor the OSK is on
+pub fn place_osk_window(window: &WebviewWindow) {
+    if let Ok(Some(monitor)) = window.current_monitor() {
+        let screen_pos = monitor.position();
+        let screen_size = monitor.size();
+        let window_size = window.outer_size().unwrap_or(tauri::PhysicalSize { width: 800, height: 300 });
+
+        const MARGIN: i32 = 25;
+
+        let x = screen_pos.x + screen_size.width as i32 - window_size.width as i32 - MARGIN;
+        let y = screen_pos.y + screen_size.height as i32 - window_size.height as i32 - MARGIN;
+
+        let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+    }
+}
+
+// Lends the OSK window to a popup (radial menu, window switcher) while the OSK is closed.
+// Returns whether it had to be shown, i.e. whether `hide_overlay` should follow.
+pub fn show_overlay(app: &AppHandle) -> bool {
+    let osk_open = app.state::<SharedAppState>().lock().unwrap().osk_open;
+    if osk_open {
+        return false;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_always_on_top(true);
+    }
+    true
+}
+
+pub fn hide_overlay(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
+}
+
+pub fn set_osk_compact(app: &AppHandle, compact: bool) {
+    let (width, height) = if compact { COMPACT_OSK_SIZE } else { OSK_SIZE };
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_size(LogicalSize::new(width, height));
+        place_osk_window(&window);
+    }
+}
@@ -6,6 +6,9 @@ use std::thread;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager};
 
+// The foreground window is a round trip to the window system; no need to ask every tick
+const TARGET_POLL: Duration = Duration::from_millis(250);
+
 pub fn init_gamepad_listener(app: AppHandle) {
     thread::spawn(move || {
         let mut gilrs = match Gilrs::new() {
@@ -29,6 +32,10 @@ pub fn init_gamepad_listener(app: AppHandle) {
 
         let mut osk_state = input_mapper::OskState::default();
         let mut mouse_state = input_mapper::MouseState::default();
+        let mut media_state = input_mapper::MediaState::default();
+        let mut target_polled = Instant::now();
+        // Whether the current mode has been drawn since the OSK last opened
+        let mut osk_shown = false;
 
         loop {
             // Process all pending events
@@ -44,13 +51,14 @@ pub fn init_gamepad_listener(app: AppHandle) {
                 }
 
                 let state_handle = app.state::<SharedAppState>();
-                let (active, osk_open) = {
+                let (active, osk_open, osk_mode) = {
                     let state = state_handle.lock().unwrap();
-                    (state.active, state.osk_open)
+                    (state.active, state.osk_open, state.profiles.active().osk_mode)
                 };
+                osk_state.set_mode(osk_mode, &app);
 
                 // Forward to mapper (lock is released now)
-                input_mapper::handle_input(&event, active, osk_open, &app, &mut enigo);
+                input_mapper::handle_input(&event, active, osk_open, &app, &mut enigo, &mut osk_state);
             }
 
             // Check combo on all connected gamepads
@@ -75,9 +83,11 @@ pub fn init_gamepad_listener(app: AppHandle) {
 
             let mut should_toggle = false;
             let mut was_active = false;
+            let osk_mode;
 
             {
                 let mut state = state_handle.lock().unwrap();
+                osk_mode = state.profiles.active().osk_mode;
 
                 if combo_pressed {
                     if !state.toggle_guard {
@@ -117,10 +127,33 @@ pub fn init_gamepad_listener(app: AppHandle) {
                 }
             }
 
-            if run_mouse_update {
-                input_mapper::update_mouse(&gilrs, &mut enigo, &mut mouse_state);
+            let popup_open = (run_mouse_update || run_osk_update)
+                && (input_mapper::update_radial(&gilrs, &app)
+                    || input_mapper::update_switcher(&gilrs, &app)
+                    || input_mapper::update_grab(&gilrs, &app)
+                    || input_mapper::update_grid_jump(&gilrs, &app, &mut enigo));
+            if popup_open {
+                // The radial menu, window switcher, window grab and cursor grid have the sticks
+                // while they are on
+            } else if run_mouse_update {
+                // The media layer turns the sticks into volume controls
+                if !input_mapper::update_media(&gilrs, &app, &mut enigo, &mut media_state) {
+                    input_mapper::update_mouse(&gilrs, &app, &mut enigo, &mut mouse_state);
+                }
             } else if run_osk_update {
-                input_mapper::update_osk_stick(&gilrs, &app, &mut osk_state);
+                if target_polled.elapsed() >= TARGET_POLL {
+                    crate::funcs::track_target(&app);
+                    target_polled = Instant::now();
+                }
+                osk_state.set_mode(osk_mode, &app);
+                if !osk_shown {
+                    osk_state.show(&app);
+                    osk_shown = true;
+                }
+                input_mapper::update_osk_stick(&gilrs, &app, &mut enigo, &mut osk_state);
+            }
+            if !run_osk_update {
+                osk_shown = false;
             }
 
             thread::sleep(Duration::from_millis(10));
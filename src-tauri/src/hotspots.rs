@@ -0,0 +1,94 @@
+use crate::app_state::SharedAppState;
+use crate::monitor;
+use crate::native_window;
+use crate::window_manager;
+use enigo::{Coordinate, Enigo, Mouse};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Manager};
+
+// Slot name -> absolute cursor position, for one monitor arrangement
+pub type HotspotSlots = BTreeMap<String, (i32, i32)>;
+
+// Built-in targets, next to the saved slots. Monitors are numbered from 1, left to right.
+const WINDOW: &str = "window";
+const LAST_CLICK: &str = "last_click";
+const MONITOR_PREFIX: &str = "monitor:";
+
+#[derive(Default)]
+pub struct HotspotState {
+    last_click: Option<(i32, i32)>,
+    // Position in the saved slots for `cycle`
+    index: usize,
+}
+
+pub fn record_click(app: &AppHandle, enigo: &Enigo) {
+    if let Ok(position) = enigo.location() {
+        app.state::<SharedAppState>().lock().unwrap().hotspots.last_click = Some(position);
+    }
+}
+
+pub fn save(app: &AppHandle, enigo: &Enigo, slot: &str) {
+    let Ok(position) = enigo.location() else {
+        return;
+    };
+    let key = monitor::layout_key(app);
+    let profiles = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        state.profiles.active_mut().hotspots.entry(key).or_default().insert(slot.to_string(), position);
+        state.profiles.clone()
+    };
+    profiles.save(app);
+}
+
+fn resolve(app: &AppHandle, slot: &str) -> Option<(i32, i32)> {
+    if slot == WINDOW {
+        let (x, y, w, h) = native_window::rect(window_manager::target(app))?;
+        return Some((x + w / 2, y + h / 2));
+    }
+    if slot == LAST_CLICK {
+        return app.state::<SharedAppState>().lock().unwrap().hotspots.last_click;
+    }
+    if let Some(number) = slot.strip_prefix(MONITOR_PREFIX) {
+        let index = number.parse::<usize>().ok()?.checked_sub(1)?;
+        return monitor::bounds(app).get(index).map(monitor::centre);
+    }
+
+    let key = monitor::layout_key(app);
+    let state_handle = app.state::<SharedAppState>();
+    let state = state_handle.lock().unwrap();
+    state.profiles.active().hotspots.get(&key)?.get(slot).copied()
+}
+
+pub fn jump(app: &AppHandle, enigo: &mut Enigo, slot: &str) {
+    match resolve(app, slot) {
+        Some((x, y)) => {
+            let _ = enigo.move_mouse(x, y, Coordinate::Abs);
+        }
+        None => eprintln!("No hotspot {} for this monitor layout", slot),
+    }
+}
+
+// Steps through the slots saved for the current monitor layout, by name
+pub fn cycle(app: &AppHandle, enigo: &mut Enigo, step: i32) {
+    let key = monitor::layout_key(app);
+    let position = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let positions: Vec<(i32, i32)> = state
+            .profiles
+            .active()
+            .hotspots
+            .get(&key)
+            .map(|slots| slots.values().copied().collect())
+            .unwrap_or_default();
+        if positions.is_empty() {
+            return;
+        }
+        let len = positions.len() as i32;
+        let index = (state.hotspots.index as i32 + step).rem_euclid(len) as usize;
+        state.hotspots.index = index;
+        positions[index]
+    };
+    let _ = enigo.move_mouse(position.0, position.1, Coordinate::Abs);
+}
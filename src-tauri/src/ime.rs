@@ -0,0 +1,360 @@
+use crate::app_state::SharedAppState;
+use crate::compose::Composed;
+use std::collections::HashMap;
+use std::fs;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Reading -> conversions, most likely first
+const BUNDLED_DICTIONARIES: [(&str, &str); 2] = [
+    ("ja", include_str!("../dictionaries/ja.json")),
+    ("zh", include_str!("../dictionaries/zh.json")),
+];
+const USER_DICTIONARY_DIR: &str = "ime";
+const CANDIDATE_COUNT: usize = 9;
+
+const ROMAJI: &[(&str, &str)] = &[
+    ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"),
+    ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+    ("sa", "さ"), ("shi", "し"), ("si", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+    ("ta", "た"), ("chi", "ち"), ("ti", "ち"), ("tsu", "つ"), ("tu", "つ"), ("te", "て"), ("to", "と"),
+    ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+    ("ha", "は"), ("hi", "ひ"), ("fu", "ふ"), ("hu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+    ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+    ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+    ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+    ("wa", "わ"), ("wo", "を"),
+    ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+    ("za", "ざ"), ("ji", "じ"), ("zi", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+    ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+    ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+    ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+    ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"),
+    ("sha", "しゃ"), ("shu", "しゅ"), ("sho", "しょ"),
+    ("cha", "ちゃ"), ("chu", "ちゅ"), ("cho", "ちょ"),
+    ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"),
+    ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+    ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"),
+    ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+    ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+    ("ja", "じゃ"), ("ju", "じゅ"), ("jo", "じょ"),
+    ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"),
+    ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+    ("-", "ー"),
+];
+
+#[derive(Default)]
+pub struct ImeState {
+    // Language -> reading -> conversions
+    dictionaries: HashMap<String, HashMap<String, Vec<String>>>,
+    // Raw keys typed for the word being composed
+    composition: String,
+    candidates: Vec<String>,
+    selected: usize,
+    // Key or text that committed the last word; its repeats and release are ours too
+    committed_by: Option<String>,
+}
+
+impl ImeState {
+    // Bundled dictionaries, with entries from `<config>/ime/<lang>.json` ranked first
+    pub fn load(&mut self, app: &AppHandle) {
+        let user_dir = app.path().app_config_dir().ok().map(|dir| dir.join(USER_DICTIONARY_DIR));
+
+        for (language, json) in BUNDLED_DICTIONARIES {
+            let mut dictionary: HashMap<String, Vec<String>> =
+                serde_json::from_str(json).expect("bundled IME dictionary is valid");
+
+            let file = format!("{}.json", language);
+            let user = user_dir.as_ref().and_then(|dir| fs::read_to_string(dir.join(&file)).ok());
+            match user.map(|json| serde_json::from_str::<HashMap<String, Vec<String>>>(&json)) {
+                Some(Ok(user)) => {
+                    for (reading, mut words) in user {
+                        let entry = dictionary.entry(reading).or_default();
+                        entry.retain(|w| !words.contains(w));
+                        words.append(entry);
+                        *entry = words;
+                    }
+                }
+                Some(Err(e)) => eprintln!("Failed to parse {}: {}", file, e),
+                None => {}
+            }
+
+            self.dictionaries.insert(language.to_string(), dictionary);
+        }
+    }
+
+    pub fn is_composing(&self) -> bool {
+        !self.composition.is_empty()
+    }
+
+    fn update_candidates(&mut self, language: &str) {
+        self.selected = 0;
+        self.candidates = match self.dictionaries.get(language) {
+            Some(dictionary) if self.is_composing() => candidates(language, &self.composition, dictionary),
+            _ => Vec::new(),
+        };
+    }
+
+    fn take_selected(&mut self) -> String {
+        let word = self.candidates.get(self.selected).cloned().unwrap_or_else(|| self.composition.clone());
+        self.cancel();
+        word
+    }
+
+    pub fn cancel(&mut self) {
+        self.composition.clear();
+        self.candidates.clear();
+        self.selected = 0;
+    }
+}
+
+fn ime_language(language: Option<&str>) -> Option<&str> {
+    language.filter(|l| BUNDLED_DICTIONARIES.iter().any(|(lang, _)| lang == l))
+}
+
+// Romaji to hiragana, longest match first. Double consonants become っ, and n before a
+// consonant or doubled becomes ん. Whatever can't be converted yet stays as romaji.
+fn romaji_to_kana(romaji: &str) -> String {
+    let chars: Vec<char> = romaji.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    'outer: while i < chars.len() {
+        for len in (1..=3).rev() {
+            if i + len > chars.len() {
+                continue;
+            }
+            let chunk: String = chars[i..i + len].iter().collect();
+            if let Some((_, kana)) = ROMAJI.iter().find(|(r, _)| *r == chunk) {
+                out.push_str(kana);
+                i += len;
+                continue 'outer;
+            }
+        }
+
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let is_vowel = |c: char| "aiueo".contains(c);
+        if c == 'n' && next == Some('n') {
+            // "nn" is ん, unless the second n starts the next syllable as in "konnichi"
+            out.push('ん');
+            i += if chars.get(i + 2).is_some_and(|&c| is_vowel(c) || c == 'y') { 1 } else { 2 };
+            continue;
+        } else if c == 'n' && next.is_some_and(|n| !is_vowel(n) && n != 'y') {
+            out.push('ん');
+        } else if next == Some(c) && !is_vowel(c) {
+            out.push('っ');
+        } else {
+            out.push(c);
+        }
+        i += 1;
+    }
+    out
+}
+
+fn to_katakana(hiragana: &str) -> String {
+    hiragana
+        .chars()
+        .map(|c| match c {
+            '\u{3041}'..='\u{3096}' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+// Exact conversions of the whole reading, then the reading split greedily into the
+// longest dictionary words, then the reading itself
+fn candidates(language: &str, composition: &str, dictionary: &HashMap<String, Vec<String>>) -> Vec<String> {
+    let reading = match language {
+        "ja" => {
+            // A trailing n is ん once the word is converted
+            let mut kana = romaji_to_kana(composition);
+            if kana.ends_with('n') {
+                kana.pop();
+                kana.push('ん');
+            }
+            kana
+        }
+        // Apostrophes only separate syllables
+        _ => composition.replace('\'', ""),
+    };
+
+    let mut out: Vec<String> = dictionary.get(&reading).cloned().unwrap_or_default();
+
+    let chars: Vec<char> = reading.chars().collect();
+    let mut segmented = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let found = (i + 1..=chars.len()).rev().find_map(|end| {
+            let part: String = chars[i..end].iter().collect();
+            dictionary.get(&part).and_then(|words| words.first()).map(|word| (end, word.clone()))
+        });
+        match found {
+            Some((end, word)) => {
+                segmented.push_str(&word);
+                i = end;
+            }
+            None => {
+                segmented.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    out.push(segmented);
+
+    out.push(reading.clone());
+    if language == "ja" {
+        out.push(to_katakana(&reading));
+    }
+
+    let mut unique = Vec::new();
+    for word in out {
+        if !unique.contains(&word) {
+            unique.push(word);
+        }
+    }
+    unique.truncate(CANDIDATE_COUNT);
+    unique
+}
+
+// Runs in `send_key` before anything else. On a Japanese or Chinese layout letters build
+// up a reading; space or enter commits the chosen candidate.
+pub fn intercept(app: &AppHandle, phase: &str, key: Option<&str>, text: Option<&str>) -> Composed {
+    let result = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let language = state
+            .layouts
+            .get_or_default(&state.profiles.active().layout)
+            .and_then(|l| ime_language(l.language.as_deref()).map(str::to_string));
+        let Some(language) = language else {
+            return Composed::Pass;
+        };
+        let ime = &mut state.ime;
+
+        let is_reading = |t: &str| t.chars().all(|c| c.is_ascii_lowercase() || c == '\'' || c == '-');
+        let pressed = key.or(text);
+        if phase != "down" && pressed.is_some() && ime.committed_by.as_deref() == pressed {
+            if phase == "up" {
+                ime.committed_by = None;
+            }
+            return Composed::Pending;
+        }
+        // Holding Backspace keeps deleting from the reading, then from the target
+        let phase = if phase == "repeat" && key == Some("{bksp}") && ime.is_composing() { "down" } else { phase };
+        if phase != "down" {
+            // Releases and repeats of keys that fed the reading
+            let ours = ime.is_composing() || text.is_some_and(is_reading);
+            return if ours { Composed::Pending } else { Composed::Pass };
+        }
+        ime.committed_by = None;
+
+        match (key, text) {
+            (None, Some(text)) if is_reading(text) => {
+                ime.composition.push_str(text);
+                ime.update_candidates(&language);
+                Composed::Pending
+            }
+            _ if !ime.is_composing() => return Composed::Pass,
+            (Some("{bksp}"), _) => {
+                ime.composition.pop();
+                ime.update_candidates(&language);
+                Composed::Pending
+            }
+            (Some("{esc}"), _) => {
+                ime.composition.clear();
+                ime.update_candidates(&language);
+                Composed::Pending
+            }
+            (Some("{space}" | "{enter}"), _) => {
+                ime.committed_by = pressed.map(str::to_string);
+                Composed::Done(ime.take_selected())
+            }
+            // Punctuation and digits end the word and follow it
+            (None, Some(text)) => {
+                let text = match (language.as_str(), text) {
+                    ("ja", ",") => "、",
+                    ("ja", ".") => "。",
+                    _ => text,
+                };
+                ime.committed_by = pressed.map(str::to_string);
+                Composed::Done(format!("{}{}", ime.take_selected(), text))
+            }
+            // Other special keys are held back until the word is committed
+            _ => Composed::Pending,
+        }
+    };
+    emit_state(app);
+    result
+}
+
+pub fn is_composing(app: &AppHandle) -> bool {
+    app.state::<SharedAppState>().lock().unwrap().ime.is_composing()
+}
+
+// Bumpers move through the candidate list
+pub fn step_candidate(app: &AppHandle, step: i32) {
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let ime = &mut state.ime;
+        if ime.candidates.is_empty() {
+            return;
+        }
+        let len = ime.candidates.len() as i32;
+        ime.selected = (ime.selected as i32 + step).rem_euclid(len) as usize;
+    }
+    emit_state(app);
+}
+
+// Composition and candidates for the OSK; null once the word is committed
+pub fn emit_state(app: &AppHandle) {
+    let payload = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        let ime = &state.ime;
+        ime.is_composing().then(|| {
+            serde_json::json!({
+                "composition": ime.composition,
+                "candidates": ime.candidates,
+                "selected": ime.selected,
+            })
+        })
+    };
+    let _ = app.emit("osk:ime", payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syllables_take_the_longest_match() {
+        assert_eq!(romaji_to_kana("sushi"), "すし");
+        assert_eq!(romaji_to_kana("kyouto"), "きょうと");
+        assert_eq!(romaji_to_kana("chiizu"), "ちいず");
+    }
+
+    #[test]
+    fn doubled_consonants_become_small_tsu() {
+        assert_eq!(romaji_to_kana("kitte"), "きって");
+        assert_eq!(romaji_to_kana("kippu"), "きっぷ");
+    }
+
+    #[test]
+    fn n_before_a_consonant_or_doubled_is_n() {
+        assert_eq!(romaji_to_kana("kanji"), "かんじ");
+        assert_eq!(romaji_to_kana("konnichiha"), "こんにちは");
+        assert_eq!(romaji_to_kana("onna"), "おんな");
+    }
+
+    #[test]
+    fn unfinished_syllables_stay_as_romaji() {
+        assert_eq!(romaji_to_kana("k"), "k");
+        assert_eq!(romaji_to_kana("kak"), "かk");
+        assert_eq!(romaji_to_kana("kan"), "かn");
+    }
+
+    #[test]
+    fn katakana_is_shifted_hiragana() {
+        assert_eq!(to_katakana("すし-ー"), "スシ-ー");
+    }
+}
@@ -0,0 +1,162 @@
+use crate::profile::InjectionStrategy;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyboardLayout, MapVirtualKeyExW, VkKeyScanExW, HKL, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT,
+    KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_UNICODE, MAPVK_VK_TO_VSC_EX, VIRTUAL_KEY,
+};
+use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+pub const SC_SHIFT: u16 = 0x2A;
+pub const SC_CTRL: u16 = 0x1D;
+pub const SC_ALT: u16 = 0x38;
+pub const SC_ALTGR: u16 = 0xE038;
+pub const SC_WIN: u16 = 0xE05B;
+
+pub fn modifier_scan_code(name: &str) -> Option<u16> {
+    match name {
+        "shift" => Some(SC_SHIFT),
+        "ctrl" => Some(SC_CTRL),
+        "alt" => Some(SC_ALT),
+        "win" => Some(SC_WIN),
+        _ => None,
+    }
+}
+
+pub fn scan_input(sc: u16, up: bool) -> INPUT {
+    let mut flags = KEYEVENTF_SCANCODE;
+    if up {
+        flags |= KEYEVENTF_KEYUP;
+    }
+    if (sc & 0xFF00) == 0xE000 {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+    }
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: sc & 0xFF,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+pub fn unicode_input(unit: u16, up: bool) -> INPUT {
+    let mut flags = KEYEVENTF_UNICODE;
+    if up {
+        flags |= KEYEVENTF_KEYUP;
+    }
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: unit,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+struct Keystroke {
+    sc: u16,
+    shift: bool,
+    altgr: bool,
+}
+
+// Builds down/up pairs for every character of `text` according to the profile's strategy
+pub fn text_inputs(text: &str, strategy: InjectionStrategy, target: isize) -> Vec<INPUT> {
+    let layout = target_layout(target);
+    let mut inputs = Vec::new();
+
+    for c in text.chars() {
+        let stroke = match strategy {
+            InjectionStrategy::Unicode => None,
+            InjectionStrategy::Auto | InjectionStrategy::Scancode => layout_keystroke(c, layout),
+        };
+
+        match stroke {
+            Some(stroke) => push_keystroke(&mut inputs, &stroke),
+            // Forced scan codes: the layout can't type this character, so drop it
+            None if strategy == InjectionStrategy::Scancode => {}
+            None => push_unicode(&mut inputs, c),
+        }
+    }
+
+    inputs
+}
+
+// The layout that matters is the one of the window receiving the input, not ours
+fn target_layout(target: isize) -> HKL {
+    unsafe {
+        let thread = if target != 0 { GetWindowThreadProcessId(HWND(target as _), None) } else { 0 };
+        GetKeyboardLayout(thread)
+    }
+}
+
+fn layout_keystroke(c: char, layout: HKL) -> Option<Keystroke> {
+    // VkKeyScan maps '\n' to Ctrl+Enter, we want a plain Enter
+    let c = if c == '\n' { '\r' } else { c };
+
+    let mut buf = [0u16; 2];
+    let units = c.encode_utf16(&mut buf);
+    if units.len() != 1 {
+        return None;
+    }
+
+    let result = unsafe { VkKeyScanExW(units[0], layout) };
+    if result == -1 {
+        return None;
+    }
+
+    let vk = (result as u16) & 0xFF;
+    // High byte: 1 = Shift, 2 = Ctrl, 4 = Alt. Ctrl+Alt is AltGr, anything else we don't reproduce.
+    let (shift, altgr) = match (result as u16) >> 8 {
+        0 => (false, false),
+        1 => (true, false),
+        6 => (false, true),
+        7 => (true, true),
+        _ => return None,
+    };
+
+    let sc = unsafe { MapVirtualKeyExW(vk as u32, MAPVK_VK_TO_VSC_EX, Some(layout)) } as u16;
+    if sc == 0 {
+        return None;
+    }
+
+    Some(Keystroke { sc, shift, altgr })
+}
+
+fn push_keystroke(inputs: &mut Vec<INPUT>, stroke: &Keystroke) {
+    if stroke.altgr {
+        inputs.push(scan_input(SC_CTRL, false));
+        inputs.push(scan_input(SC_ALTGR, false));
+    }
+    if stroke.shift {
+        inputs.push(scan_input(SC_SHIFT, false));
+    }
+
+    inputs.push(scan_input(stroke.sc, false));
+    inputs.push(scan_input(stroke.sc, true));
+
+    if stroke.shift {
+        inputs.push(scan_input(SC_SHIFT, true));
+    }
+    if stroke.altgr {
+        inputs.push(scan_input(SC_ALTGR, true));
+        inputs.push(scan_input(SC_CTRL, true));
+    }
+}
+
+fn push_unicode(inputs: &mut Vec<INPUT>, c: char) {
+    let mut buf = [0u16; 2];
+    for unit in c.encode_utf16(&mut buf) {
+        inputs.push(unicode_input(*unit, false));
+        inputs.push(unicode_input(*unit, true));
+    }
+}
@@ -0,0 +1,140 @@
+use super::{button_from_name, stick};
+use crate::app_state::SharedAppState;
+use crate::prediction;
+use crate::staging::{self, StagingAction};
+use crate::text_delivery;
+use enigo::{Direction, Enigo, Key, Keyboard};
+use gilrs::{Axis, Gilrs};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+const CARET_DEAD_ZONE: f32 = 0.3;
+// Arrow repeats per second just outside the dead zone and at full deflection
+const MIN_RATE: f32 = 4.0;
+const MAX_RATE: f32 = 25.0;
+
+#[derive(Default)]
+pub struct CaretState {
+    direction: Option<(i32, i32)>,
+    // Fraction of the next repeat built up so far
+    progress: f32,
+    last_tick: Option<Instant>,
+}
+
+// Right stick as arrow keys while the OSK is open. Repeats speed up with deflection;
+// the profile's select/word buttons add Shift and Ctrl.
+pub fn update(gilrs: &Gilrs, app: &AppHandle, enigo: &mut Enigo, state: &mut CaretState) {
+    let now = Instant::now();
+    let elapsed = state.last_tick.map(|t| (now - t).as_secs_f32()).unwrap_or(0.0);
+    state.last_tick = Some(now);
+
+    let (x, y) = stick(gilrs, Axis::RightStickX, Axis::RightStickY);
+    let Some(direction) = stick_direction(x, y) else {
+        state.direction = None;
+        return;
+    };
+
+    let steps = if state.direction != Some(direction) {
+        // A fresh push moves right away
+        state.direction = Some(direction);
+        state.progress = 0.0;
+        1
+    } else {
+        state.progress += elapsed * repeat_rate(x.abs().max(y.abs()));
+        let steps = state.progress.floor();
+        state.progress -= steps;
+        steps as u32
+    };
+
+    let (select_button, word_button) = {
+        let state_handle = app.state::<SharedAppState>();
+        let app_state = state_handle.lock().unwrap();
+        let profile = app_state.profiles.active();
+        (profile.caret_select_button.clone(), profile.caret_word_button.clone())
+    };
+    let held = |name: &str| {
+        button_from_name(name).is_some_and(|btn| gilrs.gamepads().any(|(_id, gamepad)| gamepad.is_pressed(btn)))
+    };
+    let (shift, ctrl) = (held(&select_button), held(&word_button));
+
+    for _ in 0..steps {
+        send_arrow(app, enigo, direction, shift, ctrl);
+    }
+}
+
+// Dominant axis only, diagonals would make the caret zig-zag
+fn stick_direction(x: f32, y: f32) -> Option<(i32, i32)> {
+    if x.abs().max(y.abs()) < CARET_DEAD_ZONE {
+        None
+    } else if x.abs() >= y.abs() {
+        Some((x.signum() as i32, 0))
+    } else {
+        // gilrs Y is positive up
+        Some((0, -y.signum() as i32))
+    }
+}
+
+// Repeats per second, eased so most of the stick's travel stays slow
+fn repeat_rate(magnitude: f32) -> f32 {
+    let t = ((magnitude - CARET_DEAD_ZONE) / (1.0 - CARET_DEAD_ZONE)).clamp(0.0, 1.0);
+    MIN_RATE + (MAX_RATE - MIN_RATE) * t * t
+}
+
+fn send_arrow(app: &AppHandle, enigo: &mut Enigo, direction: (i32, i32), shift: bool, ctrl: bool) {
+    let (name, key) = match direction {
+        (-1, _) => ("{arrowleft}", Key::LeftArrow),
+        (1, _) => ("{arrowright}", Key::RightArrow),
+        (_, -1) => ("{arrowup}", Key::UpArrow),
+        _ => ("{arrowdown}", Key::DownArrow),
+    };
+
+    // The staging buffer has no selection, but does jump by words
+    if ctrl && direction.0 != 0 && staging::enabled(app) {
+        staging::run(app, if direction.0 < 0 { StagingAction::WordLeft } else { StagingAction::WordRight });
+        return;
+    }
+    if (!shift && !ctrl) || staging::enabled(app) {
+        text_delivery::osk_key(app, name);
+        return;
+    }
+
+    prediction::observe_key(app, Some(name), None);
+    text_delivery::focus_target(app);
+    let modifiers: Vec<Key> = [(ctrl, Key::Control), (shift, Key::Shift)]
+        .into_iter()
+        .filter_map(|(held, key)| held.then_some(key))
+        .collect();
+    for modifier in &modifiers {
+        let _ = enigo.key(*modifier, Direction::Press);
+    }
+    let _ = enigo.key(key, Direction::Click);
+    for modifier in modifiers.iter().rev() {
+        let _ = enigo.key(*modifier, Direction::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zone_is_no_direction() {
+        assert_eq!(stick_direction(0.2, -0.2), None);
+    }
+
+    #[test]
+    fn dominant_axis_wins() {
+        assert_eq!(stick_direction(0.8, 0.5), Some((1, 0)));
+        assert_eq!(stick_direction(-0.9, -0.3), Some((-1, 0)));
+        assert_eq!(stick_direction(0.2, 0.9), Some((0, -1)));
+        assert_eq!(stick_direction(0.2, -0.9), Some((0, 1)));
+    }
+
+    #[test]
+    fn rate_speeds_up_with_deflection() {
+        assert_eq!(repeat_rate(CARET_DEAD_ZONE), MIN_RATE);
+        assert_eq!(repeat_rate(1.0), MAX_RATE);
+        assert_eq!(repeat_rate(1.5), MAX_RATE);
+        assert!(repeat_rate(0.5) < repeat_rate(0.8));
+    }
+}
@@ -0,0 +1,177 @@
+use super::button_name;
+use crate::funcs;
+use crate::prediction;
+use crate::text_delivery;
+use gilrs::Button;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{AppHandle, Emitter, Manager};
+
+const DEFAULT_CHORDS: &str = include_str!("../../chords/default.json");
+const USER_CHORDS_FILE: &str = "chords.json";
+
+// Buttons that take part in chords, in bit order
+const CHORD_BUTTONS: [&str; 6] = ["south", "east", "west", "north", "lb", "rb"];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChordOutput {
+    Text { text: String },
+    // simple-keyboard key name, e.g. "{bksp}"
+    Key { key: String },
+    Shift,
+    Close,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChordEntry {
+    pub buttons: Vec<String>,
+    pub output: ChordOutput,
+}
+
+#[derive(Default)]
+pub struct ChordState {
+    table: Vec<(u8, ChordEntry)>,
+    // Buttons currently down, and every button pressed since the chord started
+    held: u8,
+    chord: u8,
+    shift: bool,
+}
+
+impl ChordState {
+    // User table from the config dir if there is one, the bundled default otherwise
+    pub fn load(&mut self, app: &AppHandle) {
+        let user = app
+            .path()
+            .app_config_dir()
+            .ok()
+            .and_then(|dir| fs::read_to_string(dir.join(USER_CHORDS_FILE)).ok());
+
+        let entries: Vec<ChordEntry> = match user.map(|json| serde_json::from_str(&json)) {
+            Some(Ok(entries)) => entries,
+            Some(Err(e)) => {
+                eprintln!("Failed to parse {}: {}", USER_CHORDS_FILE, e);
+                default_chords()
+            }
+            None => default_chords(),
+        };
+
+        self.table = entries
+            .into_iter()
+            .filter_map(|entry| chord_mask(&entry.buttons).map(|mask| (mask, entry)))
+            .collect();
+    }
+}
+
+fn default_chords() -> Vec<ChordEntry> {
+    serde_json::from_str(DEFAULT_CHORDS).expect("bundled chord table is valid")
+}
+
+fn chord_mask(buttons: &[String]) -> Option<u8> {
+    let mut mask = 0;
+    for name in buttons {
+        mask |= button_bit(name)?;
+    }
+    (mask != 0).then_some(mask)
+}
+
+fn button_bit(name: &str) -> Option<u8> {
+    CHORD_BUTTONS.iter().position(|b| *b == name).map(|i| 1 << i)
+}
+
+fn mask_names(mask: u8) -> Vec<&'static str> {
+    CHORD_BUTTONS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+// Chords commit when the last button is released, so they can be pressed loosely
+pub fn handle_button(btn: Button, pressed: bool, app: &AppHandle, state: &mut ChordState) {
+    if btn == Button::Select {
+        if pressed {
+            state.shift = !state.shift;
+            emit_state(app, state);
+        }
+        return;
+    }
+
+    let Some(bit) = button_name(btn).and_then(button_bit) else {
+        return;
+    };
+
+    if pressed {
+        state.held |= bit;
+        state.chord |= bit;
+    } else {
+        state.held &= !bit;
+        if state.held == 0 && state.chord != 0 {
+            let chord = std::mem::take(&mut state.chord);
+            commit(chord, app, state);
+        }
+    }
+    emit_state(app, state);
+}
+
+fn commit(chord: u8, app: &AppHandle, state: &mut ChordState) {
+    let Some(output) = state.table.iter().find(|(mask, _)| *mask == chord).map(|(_, e)| e.output.clone()) else {
+        return;
+    };
+
+    match output {
+        ChordOutput::Text { text } => {
+            // Shift capitalises the word rather than typing it in capitals
+            let text = if state.shift { prediction::capitalized(&text) } else { text };
+            text_delivery::osk_text(app, &text);
+        }
+        ChordOutput::Key { key } => text_delivery::osk_key(app, &key),
+        ChordOutput::Shift => state.shift = !state.shift,
+        ChordOutput::Close => funcs::close_osk(app),
+    }
+}
+
+pub fn emit_state(app: &AppHandle, state: &ChordState) {
+    let _ = app.emit("osk:chord", serde_json::json!({
+        "held": mask_names(state.held),
+        "chord": mask_names(state.chord),
+        "shift": state.shift,
+    }));
+}
+
+// The full table for the on-screen cheat sheet, sent whenever the mode is shown
+pub fn emit_cheat_sheet(app: &AppHandle, state: &ChordState) {
+    let entries: Vec<&ChordEntry> = state.table.iter().map(|(_, entry)| entry).collect();
+    let _ = app.emit("osk:chord:cheat_sheet", entries);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_ignore_button_order() {
+        let a = chord_mask(&["south".to_string(), "lb".to_string()]);
+        let b = chord_mask(&["lb".to_string(), "south".to_string()]);
+        assert_eq!(a, Some(0b010001));
+        assert_eq!(a, b);
+        assert_eq!(mask_names(0b010001), ["south", "lb"]);
+    }
+
+    #[test]
+    fn unknown_or_empty_chords_are_dropped() {
+        assert_eq!(chord_mask(&["start".to_string()]), None);
+        assert_eq!(chord_mask(&[]), None);
+    }
+
+    #[test]
+    fn bundled_table_has_no_duplicate_chords() {
+        let masks: Vec<u8> = default_chords().iter().filter_map(|e| chord_mask(&e.buttons)).collect();
+        let mut unique = masks.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(masks.len(), unique.len());
+        assert_eq!(masks.len(), default_chords().len());
+    }
+}
@@ -0,0 +1,90 @@
+use super::osk::{stick_sector, DEAD_ZONE, RELEASE_ZONE};
+use super::stick;
+use crate::funcs;
+use crate::text_delivery;
+use gilrs::{Axis, Button, Gilrs};
+use tauri::{AppHandle, Emitter};
+
+// Petals clockwise from up; characters in face button order: West, North, East, South
+// (the same left/top/right/bottom order they are drawn in).
+const PETALS: [[char; 4]; 8] = [
+    ['a', 'b', 'c', 'd'],
+    ['e', 'f', 'g', 'h'],
+    ['i', 'j', 'k', 'l'],
+    ['m', 'n', 'o', 'p'],
+    ['q', 'r', 's', 't'],
+    ['u', 'v', 'w', 'x'],
+    ['y', 'z', ',', '.'],
+    ['?', '!', '\'', '-'],
+];
+
+#[derive(Default)]
+pub struct DaisywheelState {
+    pub petal: Option<usize>,
+    pub shift: bool,
+}
+
+pub fn update_stick(gilrs: &Gilrs, app: &AppHandle, state: &mut DaisywheelState) {
+    let (x, y) = stick(gilrs, Axis::LeftStickX, Axis::LeftStickY);
+    let magnitude = (x * x + y * y).sqrt();
+
+    // Keep the petal until the stick is almost centred, so letting go doesn't flicker
+    let petal = if magnitude >= DEAD_ZONE {
+        Some(stick_sector(x, y, PETALS.len()))
+    } else if magnitude >= RELEASE_ZONE {
+        state.petal
+    } else {
+        None
+    };
+
+    if petal != state.petal {
+        state.petal = petal;
+        emit_state(app, state);
+    }
+}
+
+pub fn handle_button(btn: Button, app: &AppHandle, state: &mut DaisywheelState) {
+    let slot = match btn {
+        Button::West => 0,
+        Button::North => 1,
+        Button::East => 2,
+        Button::South => 3,
+        Button::Select => {
+            state.shift = !state.shift;
+            emit_state(app, state);
+            return;
+        }
+        _ => return,
+    };
+
+    match state.petal {
+        Some(petal) => {
+            let c = PETALS[petal][slot];
+            let text: String = if state.shift { c.to_uppercase().collect() } else { c.to_string() };
+            text_delivery::osk_text(app, &text);
+        }
+        // Stick centred: the face buttons are the usual editing keys
+        None => match btn {
+            Button::West => text_delivery::osk_key(app, "{bksp}"),
+            Button::North => text_delivery::osk_key(app, "{enter}"),
+            Button::South => text_delivery::osk_key(app, "{space}"),
+            Button::East => funcs::close_osk(app),
+            _ => {}
+        },
+    }
+}
+
+pub fn emit_state(app: &AppHandle, state: &DaisywheelState) {
+    let petals: Vec<String> = PETALS
+        .iter()
+        .map(|p| {
+            let s: String = p.iter().collect();
+            if state.shift { s.to_uppercase() } else { s }
+        })
+        .collect();
+    let _ = app.emit("osk:daisywheel", serde_json::json!({
+        "petal": state.petal,
+        "petals": petals,
+        "shift": state.shift,
+    }));
+}
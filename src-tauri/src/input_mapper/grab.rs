@@ -0,0 +1,66 @@
+use super::stick;
+use crate::app_state::SharedAppState;
+use crate::native_window;
+use crate::window_manager;
+use gilrs::{Axis, Event, EventType, Gilrs};
+use tauri::{AppHandle, Manager};
+
+const DEAD_ZONE: f32 = 0.15;
+// Pixels per tick at full deflection
+const MOVE_SPEED: f32 = 25.0;
+const RESIZE_SPEED: f32 = 15.0;
+const MIN_SIZE: f32 = 100.0;
+
+// Any button lets go of the window
+pub fn handle_input(event: &Event, app: &AppHandle) {
+    if let EventType::ButtonPressed(..) = event.event {
+        window_manager::end_grab(app);
+    }
+}
+
+fn speed(value: f32, max: f32) -> f32 {
+    if value.abs() < DEAD_ZONE {
+        0.0
+    } else {
+        // Slow near the centre for fine placement, like the mouse curve
+        value.powi(3) * max
+    }
+}
+
+// Left stick moves the grabbed window, right stick resizes it. Returns false when nothing
+// is grabbed.
+pub fn update(gilrs: &Gilrs, app: &AppHandle) -> bool {
+    let (move_x, move_y) = stick(gilrs, Axis::LeftStickX, Axis::LeftStickY);
+    let (size_x, size_y) = stick(gilrs, Axis::RightStickX, Axis::RightStickY);
+
+    let moved = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let Some(grab) = state.window_manager.grab.as_mut() else {
+            return false;
+        };
+        let (x, y, w, h) = grab.rect;
+        // gilrs Y is positive up
+        let rect = (
+            x + speed(move_x, MOVE_SPEED),
+            y - speed(move_y, MOVE_SPEED),
+            (w + speed(size_x, RESIZE_SPEED)).max(MIN_SIZE),
+            (h - speed(size_y, RESIZE_SPEED)).max(MIN_SIZE),
+        );
+        let changed = rect_px(rect) != rect_px(grab.rect);
+        grab.rect = rect;
+        changed.then(|| (grab.handle, rect_px(rect)))
+    };
+
+    if let Some((handle, (x, y, w, h))) = moved {
+        if !native_window::set_rect(handle, x, y, w, h) {
+            // The window went away
+            window_manager::end_grab(app);
+        }
+    }
+    true
+}
+
+fn rect_px(rect: (f32, f32, f32, f32)) -> (i32, i32, i32, i32) {
+    (rect.0 as i32, rect.1 as i32, rect.2 as i32, rect.3 as i32)
+}
@@ -0,0 +1,239 @@
+use super::osk::{stick_sector, DEAD_ZONE, RELEASE_ZONE};
+use super::stick;
+use crate::app_state::SharedAppState;
+use crate::hotspots;
+use crate::monitor::{self, Rect as MonitorRect};
+use crate::overlay;
+use enigo::{Button as MouseButton, Coordinate, Direction, Enigo, Mouse};
+use gilrs::{Axis, Button, Event, EventType, Gilrs};
+use tauri::{AppHandle, Emitter, Manager};
+
+type Rect = (f32, f32, f32, f32);
+
+// Stick sectors clockwise from up, as (column, row) of the 3x3 grid
+const SECTOR_CELLS: [(i32, i32); 8] = [(1, 0), (2, 0), (2, 1), (2, 2), (1, 2), (0, 2), (0, 1), (0, 0)];
+const CENTRE: (i32, i32) = (1, 1);
+// Cells this small can't be split any further
+const MIN_CELL: f32 = 2.0;
+
+// keynav-style jumping over a 3x3 grid on the monitor. Every D-pad press keeps the half
+// of the area on that side; North zooms into the cell the stick points at. South and West
+// click like they do in system mode.
+pub struct GridJump {
+    // Physical bounds of the monitor the cursor was on
+    monitor: MonitorRect,
+    // Area being split, relative to the monitor. Earlier areas are kept for undo.
+    region: Rect,
+    history: Vec<Rect>,
+    cell: (i32, i32),
+    stick_cell: Option<(i32, i32)>,
+}
+
+impl GridJump {
+    fn cell_rect(&self) -> Rect {
+        let (x, y, w, h) = self.region;
+        let (cw, ch) = (w / 3.0, h / 3.0);
+        (x + cw * self.cell.0 as f32, y + ch * self.cell.1 as f32, cw, ch)
+    }
+
+    // Narrows to `region` unless that would make the cells too small; the old area goes on
+    // the undo stack
+    fn zoom(&mut self, region: Rect) {
+        if region.2 / 3.0 >= MIN_CELL && region.3 / 3.0 >= MIN_CELL {
+            self.history.push(self.region);
+            self.region = region;
+        }
+        self.cell = CENTRE;
+    }
+
+    // Half of the area on the `dx`/`dy` side
+    fn half(&self, dx: i32, dy: i32) -> Rect {
+        let (x, y, w, h) = self.region;
+        match (dx, dy) {
+            (-1, _) => (x, y, w / 2.0, h),
+            (1, _) => (x + w / 2.0, y, w / 2.0, h),
+            (_, -1) => (x, y, w, h / 2.0),
+            _ => (x, y + h / 2.0, w, h / 2.0),
+        }
+    }
+
+    // Centre of the picked cell in absolute screen pixels
+    fn target(&self) -> (i32, i32) {
+        let (x, y, w, h) = self.cell_rect();
+        (self.monitor.0 + (x + w / 2.0) as i32, self.monitor.1 + (y + h / 2.0) as i32)
+    }
+}
+
+pub fn is_active(app: &AppHandle) -> bool {
+    app.state::<SharedAppState>().lock().unwrap().grid_jump.is_some()
+}
+
+pub fn open(app: &AppHandle, enigo: &mut Enigo) {
+    if is_active(app) {
+        return;
+    }
+    let (x, y) = enigo.location().unwrap_or((0, 0));
+    let Some(monitor) = monitor::at(app, x, y) else {
+        return;
+    };
+    let grid = GridJump {
+        monitor,
+        region: (0.0, 0.0, monitor.2 as f32, monitor.3 as f32),
+        history: Vec::new(),
+        cell: CENTRE,
+        stick_cell: None,
+    };
+    let target = grid.target();
+    app.state::<SharedAppState>().lock().unwrap().grid_jump = Some(grid);
+
+    overlay::show(app, monitor);
+    let _ = enigo.move_mouse(target.0, target.1, Coordinate::Abs);
+    emit_state(app);
+}
+
+fn close(app: &AppHandle) {
+    app.state::<SharedAppState>().lock().unwrap().grid_jump = None;
+    emit_state(app);
+    overlay::hide(app);
+}
+
+// Applies `f` to the grid, then puts the cursor on the picked cell
+fn update_grid(app: &AppHandle, enigo: &mut Enigo, f: impl FnOnce(&mut GridJump)) {
+    let target = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let Some(grid) = state.grid_jump.as_mut() else {
+            return;
+        };
+        f(grid);
+        grid.target()
+    };
+    let _ = enigo.move_mouse(target.0, target.1, Coordinate::Abs);
+    emit_state(app);
+}
+
+pub fn handle_input(event: &Event, app: &AppHandle, enigo: &mut Enigo) {
+    let EventType::ButtonPressed(btn, _) = event.event else {
+        return;
+    };
+    let narrow = |dx: i32, dy: i32| move |grid: &mut GridJump| grid.zoom(grid.half(dx, dy));
+    match btn {
+        Button::DPadUp => update_grid(app, enigo, narrow(0, -1)),
+        Button::DPadDown => update_grid(app, enigo, narrow(0, 1)),
+        Button::DPadLeft => update_grid(app, enigo, narrow(-1, 0)),
+        Button::DPadRight => update_grid(app, enigo, narrow(1, 0)),
+        Button::North => update_grid(app, enigo, |grid| grid.zoom(grid.cell_rect())),
+        Button::LeftTrigger => update_grid(app, enigo, |grid| {
+            if let Some(region) = grid.history.pop() {
+                grid.region = region;
+                grid.cell = CENTRE;
+            }
+        }),
+        Button::South | Button::West => {
+            // The cursor is already on the cell; get the overlay out of the way first
+            close(app);
+            let button = if btn == Button::South { MouseButton::Left } else { MouseButton::Right };
+            hotspots::record_click(app, enigo);
+            let _ = enigo.button(button, Direction::Click);
+        }
+        Button::East | Button::Start => close(app),
+        _ => {}
+    }
+}
+
+// The left stick points at one of the outer cells, centred is the middle one. It only
+// takes over when it moves, so the centre cell stays picked after a D-pad narrow. Returns false when the grid is off.
+pub fn update(gilrs: &Gilrs, app: &AppHandle, enigo: &mut Enigo) -> bool {
+    let (x, y) = stick(gilrs, Axis::LeftStickX, Axis::LeftStickY);
+    let magnitude = (x * x + y * y).sqrt();
+
+    let picked = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let Some(grid) = state.grid_jump.as_mut() else {
+            return false;
+        };
+        let stick_cell = if magnitude >= DEAD_ZONE {
+            Some(SECTOR_CELLS[stick_sector(x, y, SECTOR_CELLS.len())])
+        } else if magnitude >= RELEASE_ZONE {
+            grid.stick_cell
+        } else {
+            None
+        };
+        if stick_cell == grid.stick_cell {
+            return true;
+        }
+        grid.stick_cell = stick_cell;
+        stick_cell.unwrap_or(CENTRE)
+    };
+    update_grid(app, enigo, |grid| grid.cell = picked);
+    true
+}
+
+// Null once the grid is closed
+fn emit_state(app: &AppHandle) {
+    let payload = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.grid_jump.as_ref().map(|grid| {
+            let (x, y, w, h) = grid.region;
+            serde_json::json!({
+                "region": [x, y, w, h],
+                "cell": [grid.cell.0, grid.cell.1],
+            })
+        })
+    };
+    let _ = app.emit("osk:grid_jump", payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> GridJump {
+        GridJump {
+            monitor: (1920, 0, 1920, 1080),
+            region: (0.0, 0.0, 1920.0, 1080.0),
+            history: Vec::new(),
+            cell: CENTRE,
+            stick_cell: None,
+        }
+    }
+
+    #[test]
+    fn target_is_the_centre_of_the_picked_cell_on_screen() {
+        let mut grid = grid();
+        assert_eq!(grid.target(), (2880, 540));
+        grid.cell = (0, 0);
+        assert_eq!(grid.cell_rect(), (0.0, 0.0, 640.0, 360.0));
+        assert_eq!(grid.target(), (2240, 180));
+    }
+
+    #[test]
+    fn each_dpad_press_keeps_one_half() {
+        let mut grid = grid();
+        grid.zoom(grid.half(1, 0));
+        assert_eq!(grid.region, (960.0, 0.0, 960.0, 1080.0));
+        grid.zoom(grid.half(0, 1));
+        assert_eq!(grid.region, (960.0, 540.0, 960.0, 540.0));
+        assert_eq!(grid.history.len(), 2);
+    }
+
+    #[test]
+    fn zooming_into_a_cell_recentres_the_pick() {
+        let mut grid = grid();
+        grid.cell = (2, 0);
+        grid.zoom(grid.cell_rect());
+        assert_eq!(grid.region, (1280.0, 0.0, 640.0, 360.0));
+        assert_eq!(grid.cell, CENTRE);
+    }
+
+    #[test]
+    fn areas_stop_shrinking_at_the_minimum_cell() {
+        let mut grid = grid();
+        grid.region = (0.0, 0.0, 9.0, 9.0);
+        grid.zoom(grid.half(-1, 0));
+        assert_eq!(grid.region, (0.0, 0.0, 9.0, 9.0));
+        assert!(grid.history.is_empty());
+    }
+}
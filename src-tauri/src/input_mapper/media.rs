@@ -0,0 +1,155 @@
+use super::{button_name, stick};
+use crate::actions::{self, Action};
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use enigo::{Direction, Enigo, Key, Keyboard};
+use gilrs::{Axis, Button, Event, EventType, Gilrs};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+
+const VOLUME_DEAD_ZONE: f32 = 0.2;
+// Volume steps per second just outside the dead zone and at full deflection
+const MIN_RATE: f32 = 2.0;
+const MAX_RATE: f32 = 20.0;
+
+#[derive(Default)]
+pub struct MediaState {
+    // Fraction of the next volume step built up so far
+    progress: f32,
+    last_tick: Option<Instant>,
+}
+
+pub fn is_active(app: &AppHandle) -> bool {
+    app.state::<SharedAppState>().lock().unwrap().media_layer.is_some()
+}
+
+// The layer borrows the OSK window for its OSD
+pub fn toggle(app: &AppHandle) {
+    let was_on = app.state::<SharedAppState>().lock().unwrap().media_layer.take();
+    match was_on {
+        Some(shown_window) => {
+            if shown_window {
+                funcs::hide_overlay(app);
+            }
+        }
+        None => {
+            let shown_window = funcs::show_overlay(app);
+            app.state::<SharedAppState>().lock().unwrap().media_layer = Some(shown_window);
+        }
+    }
+    emit_osd(app, None);
+}
+
+// Buttons go through the profile's media map instead of the system one
+pub fn handle_input(event: &Event, app: &AppHandle, enigo: &mut Enigo) {
+    let (btn, direction) = match event.event {
+        EventType::ButtonPressed(b, _) => (b, Direction::Press),
+        EventType::ButtonReleased(b, _) => (b, Direction::Release),
+        _ => return,
+    };
+    // Triggers are analog volume here
+    if matches!(btn, Button::LeftTrigger2 | Button::RightTrigger2) {
+        return;
+    }
+    let Some(name) = button_name(btn) else {
+        return;
+    };
+
+    let action = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.profiles.active().media_buttons.get(name).cloned()
+    };
+    let Some(action) = action else {
+        return;
+    };
+    actions::run(&action, direction, app, enigo);
+    if let (Action::Key { key, .. }, Direction::Press) = (&action, direction) {
+        emit_osd(app, Some(key));
+    }
+}
+
+// Right stick up/down and the analog triggers change the volume, faster the further they
+// are pushed. Returns false when the layer is off, leaving the sticks to the mouse.
+pub fn update(gilrs: &Gilrs, app: &AppHandle, enigo: &mut Enigo, state: &mut MediaState) -> bool {
+    if !is_active(app) {
+        state.last_tick = None;
+        return false;
+    }
+    let now = Instant::now();
+    let elapsed = state.last_tick.map(|t| (now - t).as_secs_f32()).unwrap_or(0.0);
+    state.last_tick = Some(now);
+
+    let (_, stick_y) = stick(gilrs, Axis::RightStickX, Axis::RightStickY);
+    let trigger = |btn: Button| {
+        gilrs
+            .gamepads()
+            .filter_map(|(_id, gamepad)| gamepad.button_data(btn).map(|data| data.value()))
+            .fold(0.0f32, f32::max)
+    };
+    // gilrs Y is positive up
+    let amount = (stick_y + trigger(Button::RightTrigger2) - trigger(Button::LeftTrigger2)).clamp(-1.0, 1.0);
+    let Some(rate) = volume_rate(amount) else {
+        state.progress = 0.0;
+        return true;
+    };
+    let steps = state.advance(elapsed, rate);
+
+    let (key, name) = if amount > 0.0 { (Key::VolumeUp, "volumeup") } else { (Key::VolumeDown, "volumedown") };
+    for _ in 0..steps {
+        let _ = enigo.key(key, Direction::Click);
+    }
+    if steps > 0 {
+        emit_osd(app, Some(name));
+    }
+    true
+}
+
+// Volume steps per second for a combined stick/trigger amount, None inside the dead zone
+fn volume_rate(amount: f32) -> Option<f32> {
+    if amount.abs() < VOLUME_DEAD_ZONE {
+        return None;
+    }
+    let t = (amount.abs() - VOLUME_DEAD_ZONE) / (1.0 - VOLUME_DEAD_ZONE);
+    Some(MIN_RATE + (MAX_RATE - MIN_RATE) * t)
+}
+
+impl MediaState {
+    // Whole steps due after `elapsed` seconds at `rate`; the remainder carries over
+    fn advance(&mut self, elapsed: f32, rate: f32) -> u32 {
+        self.progress += elapsed * rate;
+        let steps = self.progress.floor();
+        self.progress -= steps;
+        steps as u32
+    }
+}
+
+// What the layer last sent, for the on-screen display; null once the layer is off
+fn emit_osd(app: &AppHandle, key: Option<&str>) {
+    let payload = is_active(app).then(|| serde_json::json!({ "key": key }));
+    let _ = app.emit("osk:media", payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_grows_from_min_to_max_past_the_dead_zone() {
+        assert_eq!(volume_rate(0.1), None);
+        assert_eq!(volume_rate(-0.1), None);
+        assert_eq!(volume_rate(VOLUME_DEAD_ZONE), Some(MIN_RATE));
+        assert_eq!(volume_rate(-1.0), Some(MAX_RATE));
+        let halfway = volume_rate(0.6).unwrap();
+        assert!((halfway - (MIN_RATE + MAX_RATE) / 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn partial_steps_carry_over_between_ticks() {
+        let mut state = MediaState::default();
+        assert_eq!(state.advance(0.25, 2.0), 0);
+        assert_eq!(state.advance(0.25, 2.0), 1);
+        assert_eq!(state.advance(0.5, 10.0), 5);
+        assert!(state.progress < 1e-6);
+    }
+}
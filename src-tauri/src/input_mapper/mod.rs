@@ -1,11 +1,33 @@
+mod caret;
+mod chord;
+mod daisywheel;
+mod grab;
+mod grid_jump;
+mod media;
 mod osk;
+mod radial;
+mod split;
+mod switcher;
+mod swipe;
 mod system;
+mod t9;
 
-pub use osk::{OskState, update_osk_stick};
+pub use grab::update as update_grab;
+pub use grid_jump::{open as open_grid_jump, update as update_grid_jump, GridJump};
+pub use media::{toggle as toggle_media_layer, update as update_media, MediaState};
+pub use osk::{OskMode, OskState, update_osk_stick};
+pub use radial::{
+    default_radial_menus, open as open_radial, release as release_radial, update as update_radial, RadialMenu,
+    RadialSlot,
+};
+pub use switcher::update as update_switcher;
+pub use swipe::KeyGeometry;
 pub use system::{MouseState, update_mouse};
 
+use crate::window_manager;
+use crate::window_switcher;
 use enigo::Enigo;
-use gilrs::Event;
+use gilrs::{Axis, Button, Event, Gilrs};
 use tauri::AppHandle;
 
 pub fn handle_input(
@@ -14,16 +36,95 @@ pub fn handle_input(
     osk_open: bool,
     app: &AppHandle,
     enigo: &mut Enigo,
+    osk_state: &mut OskState,
 ) {
     if !active {
         return;
     }
 
-    if osk_open {
-        osk::handle_osk_input(event, app, enigo);
+    if grid_jump::is_active(app) {
+        grid_jump::handle_input(event, app, enigo);
+        return;
+    }
+
+    if window_manager::is_grabbing(app) {
+        grab::handle_input(event, app);
+        return;
+    }
+
+    if window_switcher::is_open(app) {
+        switcher::handle_input(event, app);
+        return;
+    }
+
+    if !osk_open && media::is_active(app) {
+        media::handle_input(event, app, enigo);
         return;
+    }
+
+    if osk_open {
+        osk::handle_osk_input(event, app, enigo, osk_state);
     } else {
         system::handle_system_input(event, app, enigo);
-        return;
     }
 }
+
+// Stable names used for button bindings in profiles
+pub fn button_name(btn: Button) -> Option<&'static str> {
+    let name = match btn {
+        Button::South => "south",
+        Button::East => "east",
+        Button::West => "west",
+        Button::North => "north",
+        Button::LeftTrigger => "lb",
+        Button::RightTrigger => "rb",
+        Button::LeftTrigger2 => "lt",
+        Button::RightTrigger2 => "rt",
+        Button::Select => "select",
+        Button::Start => "start",
+        Button::LeftThumb => "ls",
+        Button::RightThumb => "rs",
+        Button::DPadUp => "dpad_up",
+        Button::DPadDown => "dpad_down",
+        Button::DPadLeft => "dpad_left",
+        Button::DPadRight => "dpad_right",
+        _ => return None,
+    };
+    Some(name)
+}
+
+pub fn button_from_name(name: &str) -> Option<Button> {
+    const BUTTONS: [Button; 16] = [
+        Button::South,
+        Button::East,
+        Button::West,
+        Button::North,
+        Button::LeftTrigger,
+        Button::RightTrigger,
+        Button::LeftTrigger2,
+        Button::RightTrigger2,
+        Button::Select,
+        Button::Start,
+        Button::LeftThumb,
+        Button::RightThumb,
+        Button::DPadUp,
+        Button::DPadDown,
+        Button::DPadLeft,
+        Button::DPadRight,
+    ];
+    BUTTONS.into_iter().find(|btn| button_name(*btn) == Some(name))
+}
+
+// Stick vector of whichever connected pad is deflected the most, so a second idle pad doesn't cancel it out
+pub fn stick(gilrs: &Gilrs, x_axis: Axis, y_axis: Axis) -> (f32, f32) {
+    gilrs
+        .gamepads()
+        .map(|(_id, gamepad)| (gamepad.value(x_axis), gamepad.value(y_axis)))
+        .fold((0.0, 0.0), |best, (x, y)| {
+            if x * x + y * y > best.0 * best.0 + best.1 * best.1 {
+                (x, y)
+            } else {
+                best
+            }
+        })
+}
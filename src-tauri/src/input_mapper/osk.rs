@@ -1,42 +1,178 @@
+use super::caret::{self, CaretState};
+use super::chord::{self, ChordState};
+use super::daisywheel::{self, DaisywheelState};
+use super::split::{self, SplitState};
+use super::swipe::{self, SwipeState};
+use super::t9::{self, T9State};
+use super::button_name;
+use crate::actions;
+use crate::alternates;
+use crate::app_state::SharedAppState;
+use crate::clipboard_history;
 use crate::funcs;
-use enigo::Enigo;
+use crate::ime;
+use crate::layout;
+use enigo::{Direction, Enigo};
 use gilrs::{Axis, Button, Event, EventType, Gilrs};
-use tauri::{AppHandle, Emitter};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use tauri::{AppHandle, Emitter, Manager};
 
+// Stick deflection needed to pick a sector, and below which the pick is dropped again
+pub const DEAD_ZONE: f32 = 0.5;
+pub const RELEASE_ZONE: f32 = 0.35;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OskMode {
+    // D-pad / stick stepping over the simple-keyboard grid
+    #[default]
+    Grid,
+    Daisywheel,
+    // One stick per keyboard half, pointing at keys directly; triggers type
+    Split,
+    // Face buttons and bumpers pressed together, committed on release
+    Chord,
+    // Multi-tap phone keypad in a compact window
+    T9,
+    // Hold South and trace words over the keyboard with the stick
+    Swipe,
+}
+
+#[derive(Default)]
 pub struct OskState {
+    pub mode: OskMode,
     pub stick_active_x: i32, // 0, 1, -1
     pub stick_active_y: i32,
+    pub daisywheel: DaisywheelState,
+    pub split: SplitState,
+    pub chord: ChordState,
+    pub t9: T9State,
+    pub swipe: SwipeState,
+    pub caret: CaretState,
 }
 
-impl Default for OskState {
-    fn default() -> Self {
-        Self { stick_active_x: 0, stick_active_y: 0 }
+impl OskState {
+    // Called every tick with the active profile's mode; resets per-mode state on a switch
+    pub fn set_mode(&mut self, mode: OskMode, app: &AppHandle) {
+        if mode == self.mode {
+            return;
+        }
+        if self.mode == OskMode::T9 {
+            funcs::set_osk_compact(app, false);
+        }
+        *self = Self { mode, ..Self::default() };
+        self.show(app);
+    }
+
+    // Everything the frontend needs to draw the current mode; on a switch and whenever the OSK opens
+    pub fn show(&mut self, app: &AppHandle) {
+        let _ = app.emit("osk:mode_changed", self.mode);
+        match self.mode {
+            OskMode::Grid => {}
+            OskMode::Daisywheel => daisywheel::emit_state(app, &self.daisywheel),
+            OskMode::Split => {
+                self.split.refresh(app);
+                split::emit_state(app, &self.split);
+            }
+            OskMode::Chord => {
+                self.chord.load(app);
+                chord::emit_cheat_sheet(app, &self.chord);
+                chord::emit_state(app, &self.chord);
+            }
+            OskMode::T9 => {
+                funcs::set_osk_compact(app, true);
+                t9::emit_state(app, &self.t9);
+            }
+            OskMode::Swipe => swipe::emit_state(app, &self.swipe),
+        }
     }
 }
 
-pub fn handle_osk_input(event: &Event, app: &AppHandle, _enigo: &mut Enigo) {
+// Sector of the stick vector, clockwise from up. Sector 0 is centred on straight up.
+pub fn stick_sector(x: f32, y: f32, sectors: usize) -> usize {
+    let width = TAU / sectors as f32;
+    // atan2(x, y) is the clockwise angle from up (gilrs Y is positive up)
+    let bearing = (x.atan2(y) + width / 2.0).rem_euclid(TAU);
+    (bearing / width) as usize % sectors
+}
+
+pub fn handle_osk_input(event: &Event, app: &AppHandle, enigo: &mut Enigo, state: &mut OskState) {
     let (btn, phase) = match event.event {
         EventType::ButtonPressed(b, _) => (b, "down"),
         EventType::ButtonReleased(b, _) => (b, "up"),
         _ => return,
     };
 
-    match btn {
-        Button::Start => {
+    if btn == Button::Start {
+        if phase == "down" {
+            funcs::close_osk(app);
+        }
+        return;
+    }
+
+    if clipboard_history::panel_open(app) {
+        if phase == "down" {
+            handle_clipboard_button(btn, app);
+        }
+        return;
+    }
+
+    // Bumpers page through the IME candidates while a word is being composed, and
+    // through the layouts in swipe mode
+    let bumper = matches!(btn, Button::LeftTrigger | Button::RightTrigger);
+    let composing = state.mode == OskMode::Grid && ime::is_composing(app);
+    if bumper && (composing || state.mode == OskMode::Swipe) {
+        if phase == "down" {
+            let step = if btn == Button::LeftTrigger { -1 } else { 1 };
+            if composing {
+                ime::step_candidate(app, step);
+            } else {
+                layout::cycle(app, step);
+            }
+        }
+        return;
+    }
+
+    match state.mode {
+        OskMode::Grid => handle_grid_button(btn, phase, app, enigo),
+        OskMode::Daisywheel => {
             if phase == "down" {
-                funcs::close_osk(app);
+                daisywheel::handle_button(btn, app, &mut state.daisywheel);
             }
         }
-        Button::Select => {
+        OskMode::Split => {
             if phase == "down" {
-                let _ = app.emit("osk:nav:shift", ());
+                split::handle_button(btn, app, &mut state.split);
             }
         }
-        Button::East => {
-            if phase == "up" {
-                funcs::close_osk(app);
+        OskMode::Chord => chord::handle_button(btn, phase == "down", app, &mut state.chord),
+        OskMode::T9 => {
+            if phase == "down" {
+                t9::handle_button(btn, app, &mut state.t9);
             }
         }
+        OskMode::Swipe => swipe::handle_button(btn, phase == "down", app, &mut state.swipe),
+    }
+}
+
+fn handle_grid_button(btn: Button, phase: &str, app: &AppHandle, enigo: &mut Enigo) {
+    let action = button_name(btn).and_then(|name| {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.profiles.active().osk_buttons.get(name).cloned()
+    });
+    if let Some(action) = action {
+        let direction = if phase == "down" { Direction::Press } else { Direction::Release };
+        actions::run(&action, direction, app, enigo);
+        return;
+    }
+
+    match btn {
+        Button::Select if phase == "down" => {
+            let _ = app.emit("osk:nav:shift", ());
+        }
+        Button::East if phase == "up" => funcs::close_osk(app),
         Button::South => {
             let _ = app.emit("osk:nav:select", serde_json::json!({
                 "phase": phase,
@@ -45,6 +181,12 @@ pub fn handle_osk_input(event: &Event, app: &AppHandle, _enigo: &mut Enigo) {
         }
         Button::DPadUp => emit_move(app, phase, 0, -1),
         Button::DPadDown => emit_move(app, phase, 0, 1),
+        Button::DPadLeft | Button::DPadRight if alternates::is_open(app) => {
+            let dx = if btn == Button::DPadLeft { -1 } else { 1 };
+            if phase == "down" {
+                alternates::step(app, dx);
+            }
+        }
         Button::DPadLeft => emit_move(app, phase, -1, 0),
         Button::DPadRight => emit_move(app, phase, 1, 0),
         _ => {}
@@ -62,25 +204,86 @@ fn emit_move(app: &AppHandle, phase: &str, dx: i32, dy: i32) {
     }));
 }
 
-pub fn update_osk_stick(gilrs: &Gilrs, app: &AppHandle, state: &mut OskState) {
+// The clipboard history takes over the pad while it is open
+fn handle_clipboard_button(btn: Button, app: &AppHandle) {
+    match btn {
+        Button::DPadUp => clipboard_history::step(app, -1),
+        Button::DPadDown => clipboard_history::step(app, 1),
+        Button::South => clipboard_history::paste_selected(app),
+        Button::East => clipboard_history::close_panel(app),
+        Button::North => clipboard_history::toggle_pin_selected(app),
+        Button::West => clipboard_history::delete_selected(app),
+        _ => {}
+    }
+}
+
+pub fn update_osk_stick(gilrs: &Gilrs, app: &AppHandle, enigo: &mut Enigo, state: &mut OskState) {
+    if clipboard_history::panel_open(app) {
+        stick_steps(gilrs, &mut state.stick_active_x, &mut state.stick_active_y, |phase, _, dy| {
+            if phase == "down" && dy != 0 {
+                clipboard_history::step(app, dy);
+            }
+        });
+        return;
+    }
+
+    // Split typing needs the right stick for the right half
+    if state.mode != OskMode::Split {
+        caret::update(gilrs, app, enigo, &mut state.caret);
+    }
+
+    match state.mode {
+        OskMode::Grid => update_grid_stick(gilrs, app, state),
+        OskMode::Daisywheel => daisywheel::update_stick(gilrs, app, &mut state.daisywheel),
+        OskMode::Split => split::update_sticks(gilrs, app, &mut state.split),
+        OskMode::Chord => {}
+        OskMode::T9 => t9::update(gilrs, app, &mut state.t9),
+        OskMode::Swipe => swipe::update(gilrs, app, &mut state.swipe),
+    }
+}
+
+fn update_grid_stick(gilrs: &Gilrs, app: &AppHandle, state: &mut OskState) {
+    // While the accent popup is up, left/right pick a variant instead of moving focus
+    alternates::tick(app);
+    if alternates::is_open(app) {
+        stick_steps(gilrs, &mut state.stick_active_x, &mut state.stick_active_y, |phase, dx, _| {
+            if phase == "down" && dx != 0 {
+                alternates::step(app, dx);
+            }
+        });
+        return;
+    }
+
+    stick_steps(gilrs, &mut state.stick_active_x, &mut state.stick_active_y, |phase, dx, dy| {
+        emit_move(app, phase, dx, dy)
+    });
+}
+
+// Turns the left stick into four-way "down"/"up" step events, like a second D-pad
+pub fn stick_steps(
+    gilrs: &Gilrs,
+    active_x: &mut i32,
+    active_y: &mut i32,
+    mut on_step: impl FnMut(&str, i32, i32),
+) {
     for (_id, gamepad) in gilrs.gamepads() {
         let x = gamepad.value(Axis::LeftStickX);
         let y = gamepad.value(Axis::LeftStickY);
-        let threshold = 0.5;
+        let threshold = DEAD_ZONE;
 
         // X Axis
         let new_x_dir = if x > threshold { 1 } else if x < -threshold { -1 } else { 0 };
-        if new_x_dir != state.stick_active_x {
+        if new_x_dir != *active_x {
             // State changed
-            if state.stick_active_x != 0 {
+            if *active_x != 0 {
                 // Stop previous direction
-                emit_move(app, "up", state.stick_active_x, 0);
+                on_step("up", *active_x, 0);
             }
             if new_x_dir != 0 {
                 // Start new direction
-                emit_move(app, "down", new_x_dir, 0);
+                on_step("down", new_x_dir, 0);
             }
-            state.stick_active_x = new_x_dir;
+            *active_x = new_x_dir;
         }
 
         // Y Axis
@@ -88,14 +291,35 @@ pub fn update_osk_stick(gilrs: &Gilrs, app: &AppHandle, state: &mut OskState) {
         // Gilrs: "Value of axis. -1.0 to 1.0."
         // Usually Up is -1.0.
         let new_y_dir = if y > threshold { -1 } else if y < -threshold { 1 } else { 0 };
-        if new_y_dir != state.stick_active_y {
-            if state.stick_active_y != 0 {
-                emit_move(app, "up", 0, state.stick_active_y);
+        if new_y_dir != *active_y {
+            if *active_y != 0 {
+                on_step("up", 0, *active_y);
             }
             if new_y_dir != 0 {
-                emit_move(app, "down", 0, new_y_dir);
+                on_step("down", 0, new_y_dir);
             }
-            state.stick_active_y = new_y_dir;
+            *active_y = new_y_dir;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sectors_run_clockwise_from_up() {
+        assert_eq!(stick_sector(0.0, 1.0, 4), 0);
+        assert_eq!(stick_sector(1.0, 0.0, 4), 1);
+        assert_eq!(stick_sector(0.0, -1.0, 4), 2);
+        assert_eq!(stick_sector(-1.0, 0.0, 4), 3);
+    }
+
+    #[test]
+    fn first_sector_is_centred_on_up() {
+        assert_eq!(stick_sector(-0.3, 1.0, 8), 0);
+        assert_eq!(stick_sector(0.3, 1.0, 8), 0);
+        assert_eq!(stick_sector(0.7, 0.7, 8), 1);
+        assert_eq!(stick_sector(-0.7, 0.7, 8), 7);
+    }
+}
@@ -0,0 +1,209 @@
+use super::osk::{stick_sector, DEAD_ZONE, RELEASE_ZONE};
+use super::stick;
+use crate::actions::{self, Action};
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use crate::window_manager::WindowOp;
+use enigo::{Direction, Enigo};
+use gilrs::{Axis, Gilrs};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const MAX_SLOTS: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RadialSlot {
+    pub label: String,
+    pub action: Action,
+}
+
+// A menu held open by its button; slots run clockwise from up
+pub struct RadialMenu {
+    name: String,
+    slots: Vec<RadialSlot>,
+    selected: Option<usize>,
+    // The menu borrowed the OSK window as its overlay and hides it again
+    shown_window: bool,
+}
+
+fn combo(label: &str, key: &str, modifiers: &[&str]) -> RadialSlot {
+    RadialSlot {
+        label: label.to_string(),
+        action: Action::Key {
+            key: key.to_string(),
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+        },
+    }
+}
+
+// Close needs a held button, so it has no slot here
+fn window(label: &str, op: WindowOp) -> RadialSlot {
+    RadialSlot {
+        label: label.to_string(),
+        action: Action::Window { op },
+    }
+}
+
+pub fn default_radial_menus() -> BTreeMap<String, Vec<RadialSlot>> {
+    BTreeMap::from([
+        (
+            "main".to_string(),
+            vec![
+                combo("Copy", "c", &["ctrl"]),
+                combo("Paste", "v", &["ctrl"]),
+                combo("Cut", "x", &["ctrl"]),
+                combo("Undo", "z", &["ctrl"]),
+                RadialSlot {
+                    label: "Clipboard".to_string(),
+                    action: Action::ClipboardHistory,
+                },
+                combo("Desktop", "d", &["win"]),
+                combo("Select all", "a", &["ctrl"]),
+                combo("Close tab", "w", &["ctrl"]),
+            ],
+        ),
+        (
+            "windows".to_string(),
+            vec![
+                window("Maximize", WindowOp::Maximize),
+                window("Next monitor", WindowOp::NextMonitor),
+                window("Snap right", WindowOp::SnapRight),
+                RadialSlot {
+                    label: "Switch window".to_string(),
+                    action: Action::WindowSwitcher,
+                },
+                window("Minimize", WindowOp::Minimize),
+                window("Move/resize", WindowOp::Grab),
+                window("Snap left", WindowOp::SnapLeft),
+                RadialSlot {
+                    label: "Jump cursor".to_string(),
+                    action: Action::GridJump,
+                },
+            ],
+        ),
+    ])
+}
+
+pub fn open(app: &AppHandle, name: &str) {
+    let slots = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        if state.radial.is_some() {
+            return;
+        }
+        let Some(slots) = state.profiles.active().radial_menus.get(name) else {
+            eprintln!("Unknown radial menu: {}", name);
+            return;
+        };
+        slots.iter().take(MAX_SLOTS).cloned().collect()
+    };
+
+    let shown_window = funcs::show_overlay(app);
+    app.state::<SharedAppState>().lock().unwrap().radial = Some(RadialMenu {
+        name: name.to_string(),
+        slots,
+        selected: None,
+        shown_window,
+    });
+    emit_state(app);
+}
+
+// Closes the menu and fires the picked slot. A centred stick cancels.
+pub fn release(app: &AppHandle, enigo: &mut Enigo) {
+    let Some(menu) = app.state::<SharedAppState>().lock().unwrap().radial.take() else {
+        return;
+    };
+    emit_state(app);
+    if menu.shown_window {
+        funcs::hide_overlay(app);
+    }
+
+    if let Some(slot) = menu.selected.and_then(|i| menu.slots.get(i)) {
+        actions::run(&slot.action, Direction::Press, app, enigo);
+        actions::run(&slot.action, Direction::Release, app, enigo);
+    }
+}
+
+// Right stick picks the slot while the menu is open. Returns false when it is closed,
+// leaving the sticks to the usual mode.
+pub fn update(gilrs: &Gilrs, app: &AppHandle) -> bool {
+    let changed = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let Some(menu) = state.radial.as_mut() else {
+            return false;
+        };
+        if menu.slots.is_empty() {
+            return true;
+        }
+
+        let (x, y) = stick(gilrs, Axis::RightStickX, Axis::RightStickY);
+        let selected = pick(x, y, menu.slots.len(), menu.selected);
+        let changed = selected != menu.selected;
+        menu.selected = selected;
+        changed
+    };
+    if changed {
+        emit_state(app);
+    }
+    true
+}
+
+// Slot under the stick. Between the two zones the last pick holds, so it doesn't flicker
+// off as the stick springs back.
+fn pick(x: f32, y: f32, slots: usize, current: Option<usize>) -> Option<usize> {
+    let magnitude = (x * x + y * y).sqrt();
+    if magnitude >= DEAD_ZONE {
+        Some(stick_sector(x, y, slots))
+    } else if magnitude >= RELEASE_ZONE {
+        current
+    } else {
+        None
+    }
+}
+
+// Null when the menu is closed
+pub fn emit_state(app: &AppHandle) {
+    let payload = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.radial.as_ref().map(|menu| {
+            serde_json::json!({
+                "name": menu.name,
+                "slots": menu.slots.iter().map(|s| &s.label).collect::<Vec<_>>(),
+                "selected": menu.selected,
+            })
+        })
+    };
+    let _ = app.emit("osk:radial", payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stick_picks_the_slot_it_points_at() {
+        assert_eq!(pick(0.0, 1.0, 8, None), Some(0));
+        assert_eq!(pick(1.0, 0.0, 8, Some(0)), Some(2));
+        assert_eq!(pick(-1.0, 0.0, 4, None), Some(3));
+    }
+
+    #[test]
+    fn pick_holds_between_the_zones_and_drops_near_centre() {
+        assert_eq!(pick(0.4, 0.0, 8, Some(2)), Some(2));
+        assert_eq!(pick(0.4, 0.0, 8, None), None);
+        assert_eq!(pick(0.1, 0.0, 8, Some(2)), None);
+    }
+
+    #[test]
+    fn default_menus_fit_the_wheel() {
+        let menus = default_radial_menus();
+        assert!(menus.contains_key("main"));
+        assert!(menus.contains_key("windows"));
+        for slots in menus.values() {
+            assert!(!slots.is_empty() && slots.len() <= MAX_SLOTS);
+        }
+    }
+}
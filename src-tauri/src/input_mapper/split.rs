@@ -0,0 +1,190 @@
+use super::stick;
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use crate::text_delivery;
+use gilrs::{Axis, Button, Gilrs};
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Default)]
+pub struct SplitState {
+    // (row, col) each stick points at; None until the first update
+    pub left: Option<(usize, usize)>,
+    pub right: Option<(usize, usize)>,
+    pub shift: bool,
+    // Typing keys of the active layout, each row cut in two
+    left_half: Vec<Vec<String>>,
+    right_half: Vec<Vec<String>>,
+    // Layout name and shift the halves were cut from
+    cut_from: Option<(String, bool)>,
+}
+
+impl SplitState {
+    // Re-cuts the halves when the active layout or shift changed since the last time
+    pub fn refresh(&mut self, app: &AppHandle) {
+        let layout = {
+            let state_handle = app.state::<SharedAppState>();
+            let state = state_handle.lock().unwrap();
+            let name = &state.profiles.active().layout;
+            if self.cut_from.as_ref().is_some_and(|(cut, shift)| cut == name && *shift == self.shift) {
+                return;
+            }
+            state.layouts.get_or_default(name).cloned()
+        };
+        let Some(layout) = layout else {
+            return;
+        };
+
+        // Layers without a shifted twin (numbers, symbols) are uppercased when typed instead
+        let rows = if self.shift { layout.layout.get("shift") } else { None }
+            .or_else(|| layout.layout.get("default"))
+            .cloned()
+            .unwrap_or_default();
+        (self.left_half, self.right_half) = halves(&rows);
+        self.cut_from = Some((layout.name, self.shift));
+        self.left = None;
+        self.right = None;
+    }
+}
+
+// Each row's typing keys split down the middle, the odd key going left. Function keys
+// like {shift} stay on the pad's buttons.
+pub fn halves(rows: &[String]) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for row in rows {
+        let keys: Vec<String> = row
+            .split_whitespace()
+            .filter(|k| !(k.starts_with('{') && k.ends_with('}')))
+            .map(str::to_string)
+            .collect();
+        let middle = keys.len().div_ceil(2);
+        let (l, r) = keys.split_at(middle);
+        if !l.is_empty() {
+            left.push(l.to_vec());
+        }
+        if !r.is_empty() {
+            right.push(r.to_vec());
+        }
+    }
+    (left, right)
+}
+
+// Rows can be ragged: the stick picks the row first, then a key along that row
+fn key_for_stick(half: &[Vec<String>], x: f32, y: f32) -> Option<(usize, usize)> {
+    if half.is_empty() {
+        return None;
+    }
+    let (row, _) = cell_for_stick(x, y, half.len(), 1);
+    let (_, col) = cell_for_stick(x, y, 1, half[row].len());
+    Some((row, col))
+}
+
+// Absolute stick position -> grid cell. The stick's circular range is stretched onto
+// the square grid so the corner keys are reachable; a centred stick is the middle key.
+pub fn cell_for_stick(x: f32, y: f32, rows: usize, cols: usize) -> (usize, usize) {
+    let (mut x, mut y) = (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0));
+    let longest = x.abs().max(y.abs());
+    if longest > 0.0 {
+        let magnitude = (x * x + y * y).sqrt().min(1.0);
+        x = x / longest * magnitude;
+        y = y / longest * magnitude;
+    }
+    // gilrs Y is positive up, rows count down from the top
+    let col = (((x + 1.0) / 2.0) * cols as f32) as usize;
+    let row = (((1.0 - y) / 2.0) * rows as f32) as usize;
+    (row.min(rows - 1), col.min(cols - 1))
+}
+
+pub fn update_sticks(gilrs: &Gilrs, app: &AppHandle, state: &mut SplitState) {
+    state.refresh(app);
+    let (lx, ly) = stick(gilrs, Axis::LeftStickX, Axis::LeftStickY);
+    let (rx, ry) = stick(gilrs, Axis::RightStickX, Axis::RightStickY);
+
+    let left = key_for_stick(&state.left_half, lx, ly);
+    let right = key_for_stick(&state.right_half, rx, ry);
+
+    if state.left != left || state.right != right {
+        state.left = left;
+        state.right = right;
+        emit_state(app, state);
+    }
+}
+
+pub fn handle_button(btn: Button, app: &AppHandle, state: &mut SplitState) {
+    let key = match btn {
+        Button::LeftTrigger2 => state.left.map(|(r, c)| &state.left_half[r][c]),
+        Button::RightTrigger2 => state.right.map(|(r, c)| &state.right_half[r][c]),
+        Button::South => return text_delivery::osk_key(app, "{space}"),
+        Button::West => return text_delivery::osk_key(app, "{bksp}"),
+        Button::North => return text_delivery::osk_key(app, "{enter}"),
+        Button::East => return funcs::close_osk(app),
+        Button::Select => {
+            state.shift = !state.shift;
+            state.refresh(app);
+            emit_state(app, state);
+            return;
+        }
+        _ => return,
+    };
+
+    if let Some(key) = key {
+        let text = if state.shift { key.to_uppercase() } else { key.clone() };
+        text_delivery::osk_text(app, &text);
+    }
+}
+
+pub fn emit_state(app: &AppHandle, state: &SplitState) {
+    let cell = |half: &[Vec<String>], pos: Option<(usize, usize)>| {
+        pos.map(|(row, col)| serde_json::json!({ "row": row, "col": col, "key": half[row][col] }))
+    };
+    let _ = app.emit("osk:split", serde_json::json!({
+        "left": cell(&state.left_half, state.left),
+        "right": cell(&state.right_half, state.right),
+        "halves": { "left": state.left_half, "right": state.right_half },
+        "shift": state.shift,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centred_stick_is_the_middle_key() {
+        assert_eq!(cell_for_stick(0.0, 0.0, 3, 5), (1, 2));
+    }
+
+    #[test]
+    fn corners_are_reachable_on_the_circular_range() {
+        let d = std::f32::consts::FRAC_1_SQRT_2;
+        assert_eq!(cell_for_stick(-d, d, 4, 5), (0, 0));
+        assert_eq!(cell_for_stick(d, -d, 4, 5), (3, 4));
+    }
+
+    #[test]
+    fn up_is_the_top_row() {
+        assert_eq!(cell_for_stick(0.0, 1.0, 4, 5), (0, 2));
+        assert_eq!(cell_for_stick(0.0, -1.0, 4, 5), (3, 2));
+        assert_eq!(cell_for_stick(1.0, 0.0, 4, 5).1, 4);
+    }
+
+    #[test]
+    fn rows_split_down_the_middle_without_function_keys() {
+        let rows = vec![
+            "{tab} q w e r t y u i o p".to_string(),
+            "{lock} a s d f g h j k l {enter}".to_string(),
+            "{space}".to_string(),
+        ];
+        let (left, right) = halves(&rows);
+        assert_eq!(left, [vec!["q", "w", "e", "r", "t"], vec!["a", "s", "d", "f", "g"]]);
+        assert_eq!(right, [vec!["y", "u", "i", "o", "p"], vec!["h", "j", "k", "l"]]);
+    }
+
+    #[test]
+    fn ragged_rows_pick_along_their_own_length() {
+        let (left, _) = halves(&["1 2 3 4 5 6".to_string(), "a b".to_string()]);
+        assert_eq!(key_for_stick(&left, 1.0, 1.0), Some((0, 2)));
+        assert_eq!(key_for_stick(&left, 1.0, -1.0), Some((1, 0)));
+        assert_eq!(key_for_stick(&[], 0.0, 0.0), None);
+    }
+}
@@ -0,0 +1,323 @@
+use super::stick;
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use crate::text_delivery;
+use gilrs::{Axis, Button, Gilrs};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Pointer speed in key widths per tick at full deflection
+const POINTER_SPEED: f32 = 0.2;
+const STICK_DEAD_ZONE: f32 = 0.15;
+// Paths shorter than this (in key widths) are a tap on the key under the pointer
+const TAP_LENGTH: f32 = 0.5;
+// How far the path may start/end from a word's first/last key, in key widths
+const ENDPOINT_RADIUS: f32 = 1.5;
+const RESAMPLE_POINTS: usize = 32;
+const ALTERNATIVE_COUNT: usize = 4;
+
+// Centre and size of one OSK key, in the frontend's pixel coordinates
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct KeyGeometry {
+    pub key: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Default)]
+pub struct SwipeState {
+    pointer: Option<(f32, f32)>,
+    path: Vec<(f32, f32)>,
+    tracing: bool,
+    // A swiped word was just typed, so the next one needs a space in front
+    after_word: bool,
+}
+
+fn key_size(keys: &[KeyGeometry]) -> f32 {
+    let letters: Vec<f32> = keys.iter().filter(|k| k.key.chars().count() == 1).map(|k| k.width).collect();
+    if letters.is_empty() {
+        1.0
+    } else {
+        letters.iter().sum::<f32>() / letters.len() as f32
+    }
+}
+
+fn geometry(app: &AppHandle) -> Vec<KeyGeometry> {
+    app.state::<SharedAppState>().lock().unwrap().osk_geometry.clone()
+}
+
+pub fn update(gilrs: &Gilrs, app: &AppHandle, state: &mut SwipeState) {
+    let keys = geometry(app);
+    if keys.is_empty() {
+        return;
+    }
+
+    let min_x = keys.iter().map(|k| k.x - k.width / 2.0).fold(f32::MAX, f32::min);
+    let max_x = keys.iter().map(|k| k.x + k.width / 2.0).fold(f32::MIN, f32::max);
+    let min_y = keys.iter().map(|k| k.y - k.height / 2.0).fold(f32::MAX, f32::min);
+    let max_y = keys.iter().map(|k| k.y + k.height / 2.0).fold(f32::MIN, f32::max);
+
+    let (mut px, mut py) = state.pointer.unwrap_or(((min_x + max_x) / 2.0, (min_y + max_y) / 2.0));
+    let (x, y) = stick(gilrs, Axis::LeftStickX, Axis::LeftStickY);
+    let moving = x.abs() > STICK_DEAD_ZONE || y.abs() > STICK_DEAD_ZONE;
+
+    if moving {
+        let speed = POINTER_SPEED * key_size(&keys);
+        // Squared response: small deflections for precision, full deflection to cross the keyboard
+        px = (px + x * x.abs() * speed).clamp(min_x, max_x);
+        // gilrs Y is positive up, screen Y grows down
+        py = (py - y * y.abs() * speed).clamp(min_y, max_y);
+    }
+
+    let first = state.pointer.is_none();
+    state.pointer = Some((px, py));
+    if state.tracing && moving {
+        state.path.push((px, py));
+    }
+    if moving || first {
+        emit_state(app, state);
+    }
+}
+
+pub fn handle_button(btn: Button, pressed: bool, app: &AppHandle, state: &mut SwipeState) {
+    match (btn, pressed) {
+        (Button::South, true) => {
+            state.tracing = true;
+            state.path = state.pointer.into_iter().collect();
+            emit_state(app, state);
+        }
+        (Button::South, false) => {
+            state.tracing = false;
+            let path = std::mem::take(&mut state.path);
+            finish_path(&path, app, state);
+            emit_state(app, state);
+        }
+        (Button::West, true) => {
+            state.after_word = false;
+            text_delivery::osk_key(app, "{bksp}");
+        }
+        (Button::North, true) => {
+            state.after_word = false;
+            text_delivery::osk_key(app, "{space}");
+        }
+        (Button::East, true) => funcs::close_osk(app),
+        _ => {}
+    }
+}
+
+fn finish_path(path: &[(f32, f32)], app: &AppHandle, state: &mut SwipeState) {
+    let keys = geometry(app);
+    let size = key_size(&keys);
+
+    // Barely moved: type the key under the pointer
+    if path_length(path) < TAP_LENGTH * size {
+        let Some(&(x, y)) = path.last() else {
+            return;
+        };
+        if let Some(key) = nearest_key(&keys, x, y) {
+            state.after_word = false;
+            if key.key.starts_with('{') {
+                text_delivery::osk_key(app, &key.key);
+            } else {
+                text_delivery::osk_text(app, &key.key);
+            }
+        }
+        return;
+    }
+
+    let (words, word_typed) = {
+        let state_handle = app.state::<SharedAppState>();
+        let app_state = state_handle.lock().unwrap();
+        let predictor = &app_state.predictor;
+        (predictor.ranked_words(|_| true, usize::MAX), !predictor.current_word().is_empty())
+    };
+    let mut matches = decode(path, &keys, &words).into_iter();
+    let Some(best) = matches.next() else {
+        return;
+    };
+
+    // Accepting an alternative already typed the space and ended the word
+    if state.after_word && word_typed {
+        text_delivery::osk_key(app, "{space}");
+    }
+    text_delivery::osk_text(app, &best);
+    state.after_word = true;
+
+    // Alternatives replace the word through `accept_suggestion`
+    let alternatives: Vec<String> = matches.take(ALTERNATIVE_COUNT).collect();
+    let _ = app.emit("osk:suggestions", serde_json::json!({
+        "prefix": best,
+        "suggestions": alternatives,
+    }));
+}
+
+fn nearest_key(keys: &[KeyGeometry], x: f32, y: f32) -> Option<&KeyGeometry> {
+    keys.iter().min_by(|a, b| {
+        let da = (a.x - x).powi(2) + (a.y - y).powi(2);
+        let db = (b.x - x).powi(2) + (b.y - y).powi(2);
+        da.total_cmp(&db)
+    })
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn path_length(path: &[(f32, f32)]) -> f32 {
+    path.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+// `n` points evenly spaced along the polyline
+fn resample(path: &[(f32, f32)], n: usize) -> Vec<(f32, f32)> {
+    let total = path_length(path);
+    if path.len() < 2 || total == 0.0 {
+        return vec![path.first().copied().unwrap_or_default(); n];
+    }
+
+    let step = total / (n - 1) as f32;
+    let mut out = vec![path[0]];
+    let mut travelled = 0.0;
+    let mut next = step;
+    for w in path.windows(2) {
+        let segment = distance(w[0], w[1]);
+        while out.len() < n && travelled + segment >= next && segment > 0.0 {
+            let t = (next - travelled) / segment;
+            out.push((w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t));
+            next += step;
+        }
+        travelled += segment;
+    }
+    // Float error can leave us one short of the end point
+    while out.len() < n {
+        out.push(*path.last().unwrap());
+    }
+    out
+}
+
+// Ranks `words` (most frequent first) by how closely the line through their keys
+// follows `path`. Pure, so it can be checked against synthetic paths.
+pub fn decode(path: &[(f32, f32)], keys: &[KeyGeometry], words: &[String]) -> Vec<String> {
+    let (Some(&start), Some(&end)) = (path.first(), path.last()) else {
+        return Vec::new();
+    };
+
+    let centers: HashMap<char, (f32, f32)> = keys
+        .iter()
+        .filter_map(|k| {
+            let mut chars = k.key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some((c.to_lowercase().next().unwrap_or(c), (k.x, k.y))),
+                _ => None,
+            }
+        })
+        .collect();
+    let size = key_size(keys);
+    let sampled_path = resample(path, RESAMPLE_POINTS);
+
+    let mut scored: Vec<(f32, &String)> = words
+        .iter()
+        .enumerate()
+        .filter_map(|(rank, word)| {
+            let mut ideal: Vec<(f32, f32)> = Vec::new();
+            for c in word.chars() {
+                let center = *centers.get(&c)?;
+                if ideal.last() != Some(&center) {
+                    ideal.push(center);
+                }
+            }
+            if distance(start, *ideal.first()?) > ENDPOINT_RADIUS * size
+                || distance(end, *ideal.last()?) > ENDPOINT_RADIUS * size
+            {
+                return None;
+            }
+
+            let sampled_ideal = resample(&ideal, RESAMPLE_POINTS);
+            let mean = sampled_path
+                .iter()
+                .zip(&sampled_ideal)
+                .map(|(a, b)| distance(*a, *b))
+                .sum::<f32>()
+                / RESAMPLE_POINTS as f32;
+            // Shape distance in key widths, nudged towards common words
+            let score = mean / size + 0.1 * (1.0 + rank as f32).ln();
+            Some((score, word))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+    scored.into_iter().map(|(_, w)| w.clone()).take(ALTERNATIVE_COUNT + 1).collect()
+}
+
+pub fn emit_state(app: &AppHandle, state: &SwipeState) {
+    let _ = app.emit("osk:swipe", serde_json::json!({
+        "pointer": state.pointer.map(|(x, y)| serde_json::json!({ "x": x, "y": y })),
+        "tracing": state.tracing,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit-sized QWERTY keys, each row shifted half a key further right
+    fn qwerty() -> Vec<KeyGeometry> {
+        ["qwertyuiop", "asdfghjkl", "zxcvbnm"]
+            .iter()
+            .enumerate()
+            .flat_map(|(row, letters)| {
+                letters.chars().enumerate().map(move |(col, c)| KeyGeometry {
+                    key: c.to_string(),
+                    x: 0.5 + col as f32 + row as f32 * 0.5,
+                    y: 0.5 + row as f32,
+                    width: 1.0,
+                    height: 1.0,
+                })
+            })
+            .collect()
+    }
+
+    fn trace(keys: &[KeyGeometry], word: &str) -> Vec<(f32, f32)> {
+        word.chars()
+            .map(|c| keys.iter().find(|k| k.key == c.to_string()).map(|k| (k.x, k.y)).unwrap())
+            .collect()
+    }
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn traced_word_ranks_first() {
+        let keys = qwerty();
+        let ranked = decode(&trace(&keys, "the"), &keys, &words(&["tie", "toe", "the"]));
+        assert_eq!(ranked[0], "the");
+        assert_eq!(ranked.len(), 3);
+    }
+
+    #[test]
+    fn shape_beats_frequency() {
+        let keys = qwerty();
+        let ranked = decode(&trace(&keys, "hello"), &keys, &words(&["ho", "hello"]));
+        assert_eq!(ranked, ["hello", "ho"]);
+    }
+
+    #[test]
+    fn words_must_start_and_end_near_the_path() {
+        let keys = qwerty();
+        assert!(decode(&trace(&keys, "the"), &keys, &words(&["she", "thy"])).is_empty());
+    }
+
+    #[test]
+    fn doubled_letters_need_no_loop() {
+        let keys = qwerty();
+        assert_eq!(decode(&trace(&keys, "se"), &keys, &words(&["sea", "see"])), ["see"]);
+    }
+
+    #[test]
+    fn empty_path_matches_nothing() {
+        assert!(decode(&[], &qwerty(), &words(&["the"])).is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+use super::osk::stick_steps;
+use crate::app_state::SharedAppState;
+use crate::window_switcher;
+use gilrs::{Button, Event, EventType, Gilrs};
+use tauri::{AppHandle, Manager};
+
+// The window switcher takes the pad while it is open, in system and OSK mode alike
+pub fn handle_input(event: &Event, app: &AppHandle) {
+    let EventType::ButtonPressed(btn, _) = event.event else {
+        return;
+    };
+    match btn {
+        Button::DPadUp | Button::DPadLeft | Button::LeftTrigger => window_switcher::step(app, -1),
+        Button::DPadDown | Button::DPadRight | Button::RightTrigger => window_switcher::step(app, 1),
+        Button::South => window_switcher::activate_selected(app),
+        Button::East | Button::Start => {
+            window_switcher::close(app);
+        }
+        _ => {}
+    }
+}
+
+// Left stick steps through the list. Returns false when the switcher is closed.
+pub fn update(gilrs: &Gilrs, app: &AppHandle) -> bool {
+    let mut steps = 0;
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let Some(switcher) = state.switcher.as_mut() else {
+            return false;
+        };
+        stick_steps(gilrs, &mut switcher.stick_x, &mut switcher.stick_y, |phase, dx, dy| {
+            if phase == "down" {
+                steps += dx + dy;
+            }
+        });
+    }
+    if steps != 0 {
+        window_switcher::step(app, steps);
+    }
+    true
+}
@@ -1,7 +1,13 @@
-use crate::funcs;
-use enigo::{Axis as EnigoAxis, Button as MouseButton, Coordinate, Direction, Enigo, Key, Keyboard, Mouse};
-use gilrs::{Axis, Button, Event, EventType, Gilrs};
-use tauri::AppHandle;
+use crate::actions;
+use crate::app_state::SharedAppState;
+use crate::monitor::{self, CursorEdge, MonitorInfo};
+use enigo::{Axis as EnigoAxis, Coordinate, Direction, Enigo, Mouse};
+use gilrs::{Axis, Event, EventType, Gilrs};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// Monitors get plugged in and rearranged; the list is looked up again this often
+const MONITOR_REFRESH: Duration = Duration::from_secs(2);
 
 pub fn handle_system_input(event: &Event, app: &AppHandle, enigo: &mut Enigo) {
     let (btn, direction) = match event.event {
@@ -10,43 +16,18 @@ pub fn handle_system_input(event: &Event, app: &AppHandle, enigo: &mut Enigo) {
         _ => return,
     };
 
-    match btn {
-        Button::Start => {
-            if direction == Direction::Press {
-                funcs::open_osk(app);
-            }
-        }
-        Button::South => {
-            let _ = enigo.button(MouseButton::Left, direction);
-        }
-        Button::East => {
-            let _ = enigo.key(Key::Backspace, direction);
-        }
-        Button::West => {
-            let _ = enigo.button(MouseButton::Right, direction);
-        }
-        Button::North => {
-            let _ = enigo.key(Key::Return, direction);
-        }
-        Button::LeftTrigger => {
-            let _ = enigo.key(Key::LeftArrow, direction);
-        }
-        Button::RightTrigger => {
-            let _ = enigo.key(Key::RightArrow, direction);
-        }
-        Button::DPadUp => {
-            let _ = enigo.key(Key::UpArrow, direction);
-        }
-        Button::DPadDown => {
-            let _ = enigo.key(Key::DownArrow, direction);
-        }
-        Button::DPadLeft => {
-            let _ = enigo.key(Key::LeftArrow, direction);
-        }
-        Button::DPadRight => {
-            let _ = enigo.key(Key::RightArrow, direction);
-        }
-        _ => {}
+    let Some(name) = super::button_name(btn) else {
+        return;
+    };
+
+    let action = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.profiles.active().system_buttons.get(name).cloned()
+    };
+
+    if let Some(action) = action {
+        actions::run(&action, direction, app, enigo);
     }
 }
 
@@ -56,9 +37,28 @@ pub struct MouseState {
     pub y_remainder: f32,
     pub scroll_x_remainder: f32,
     pub scroll_y_remainder: f32,
+    monitors: Vec<MonitorInfo>,
+    monitors_read: Option<Instant>,
+}
+
+impl MouseState {
+    fn monitors(&mut self, app: &AppHandle) -> &[MonitorInfo] {
+        if self.monitors_read.is_none_or(|t| t.elapsed() >= MONITOR_REFRESH) {
+            self.monitors = monitor::list(app);
+            self.monitors_read = Some(Instant::now());
+        }
+        &self.monitors
+    }
 }
 
-pub fn update_mouse(gilrs: &Gilrs, enigo: &mut Enigo, state: &mut MouseState) {
+pub fn update_mouse(gilrs: &Gilrs, app: &AppHandle, enigo: &mut Enigo, state: &mut MouseState) {
+    let (scaling, edge) = {
+        let state_handle = app.state::<SharedAppState>();
+        let app_state = state_handle.lock().unwrap();
+        let profile = app_state.profiles.active();
+        (profile.cursor_speed_scaling, profile.cursor_edge)
+    };
+
     for (_id, gamepad) in gilrs.gamepads() {
         let axis_x = gamepad.value(Axis::LeftStickX);
         let axis_y = gamepad.value(Axis::LeftStickY);
@@ -67,8 +67,18 @@ pub fn update_mouse(gilrs: &Gilrs, enigo: &mut Enigo, state: &mut MouseState) {
             let base_sens = 1.0;
             let accel_sens = 24.0;
 
-            let raw_x = axis_x * base_sens + axis_x.powi(3) * accel_sens;
-            let raw_y = -axis_y * base_sens + (-axis_y).powi(3) * accel_sens;
+            // Speed follows the monitor the cursor is on
+            let location = enigo.location().ok();
+            let speed = match location {
+                Some((x, y)) => {
+                    let monitors = state.monitors(app);
+                    monitors.get(monitor::index_at(monitors, x, y)).map_or(1.0, |m| m.speed(scaling))
+                }
+                None => 1.0,
+            };
+
+            let raw_x = (axis_x * base_sens + axis_x.powi(3) * accel_sens) * speed;
+            let raw_y = (-axis_y * base_sens + (-axis_y).powi(3) * accel_sens) * speed;
 
             let total_x = raw_x + state.x_remainder;
             let total_y = raw_y + state.y_remainder;
@@ -80,7 +90,16 @@ pub fn update_mouse(gilrs: &Gilrs, enigo: &mut Enigo, state: &mut MouseState) {
             state.y_remainder = total_y - move_y as f32;
 
             if move_x != 0 || move_y != 0 {
-                let _ = enigo.move_mouse(move_x, move_y, Coordinate::Rel);
+                match location {
+                    Some(from) if edge != CursorEdge::Stop => {
+                        let to = (from.0 + move_x, from.1 + move_y);
+                        let to = monitor::constrain(state.monitors(app), edge, from, to);
+                        let _ = enigo.move_mouse(to.0, to.1, Coordinate::Abs);
+                    }
+                    _ => {
+                        let _ = enigo.move_mouse(move_x, move_y, Coordinate::Rel);
+                    }
+                }
             }
         } else {
             state.x_remainder = 0.0;
@@ -0,0 +1,274 @@
+use super::osk::stick_steps;
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use crate::text_delivery;
+use gilrs::{Button, Gilrs};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+const CANDIDATE_COUNT: usize = 5;
+
+// Phone keypad: 3x3 grid plus 0 centred underneath. 0 is space.
+const ROWS: usize = 4;
+const COLS: usize = 3;
+const CELLS: [[&str; COLS]; ROWS] = [
+    [".,?!'", "abc", "def"],
+    ["ghi", "jkl", "mno"],
+    ["pqrs", "tuv", "wxyz"],
+    ["", " ", ""],
+];
+
+struct Pending {
+    cell: (usize, usize),
+    index: usize,
+    since: Instant,
+}
+
+pub struct T9State {
+    pub focus: (usize, usize),
+    pub shift: bool,
+    pending: Option<Pending>,
+    // Predictive mode: cells pressed for the current word, and the chosen candidate
+    sequence: Vec<(usize, usize)>,
+    candidate: usize,
+    stick_x: i32,
+    stick_y: i32,
+}
+
+impl Default for T9State {
+    fn default() -> Self {
+        Self {
+            focus: (1, 1),
+            shift: false,
+            pending: None,
+            sequence: Vec::new(),
+            candidate: 0,
+            stick_x: 0,
+            stick_y: 0,
+        }
+    }
+}
+
+fn settings(app: &AppHandle) -> (bool, Duration) {
+    let state_handle = app.state::<SharedAppState>();
+    let state = state_handle.lock().unwrap();
+    let profile = state.profiles.active();
+    (profile.t9_predictive, Duration::from_millis(profile.t9_timeout_ms))
+}
+
+fn cell_letters(cell: (usize, usize)) -> &'static str {
+    CELLS[cell.0][cell.1]
+}
+
+fn cell_for_char(c: char) -> Option<(usize, usize)> {
+    let c = c.to_lowercase().next()?;
+    (0..ROWS)
+        .flat_map(|r| (0..COLS).map(move |col| (r, col)))
+        .find(|&cell| cell_letters(cell).contains(c))
+}
+
+fn step_focus(state: &mut T9State, dx: i32, dy: i32) {
+    let (row, col) = state.focus;
+    let row = (row as i32 + dy).clamp(0, ROWS as i32 - 1) as usize;
+    let mut col = (col as i32 + dx).clamp(0, COLS as i32 - 1) as usize;
+    // The bottom row only has the middle key
+    if row == ROWS - 1 {
+        if dx != 0 && state.focus.0 == ROWS - 1 {
+            return;
+        }
+        col = 1;
+    }
+    state.focus = (row, col);
+}
+
+// Stick navigation is the same four-way stepping the grid OSK uses
+pub fn update(gilrs: &Gilrs, app: &AppHandle, state: &mut T9State) {
+    let mut moved = false;
+    let (mut stick_x, mut stick_y) = (state.stick_x, state.stick_y);
+    stick_steps(gilrs, &mut stick_x, &mut stick_y, |phase, dx, dy| {
+        if phase == "down" {
+            step_focus(state, dx, dy);
+            moved = true;
+        }
+    });
+    state.stick_x = stick_x;
+    state.stick_y = stick_y;
+
+    let (_, timeout) = settings(app);
+    let expired = state.pending.as_ref().is_some_and(|p| p.since.elapsed() >= timeout);
+    if expired {
+        commit_pending(app, state);
+    }
+
+    if moved || expired {
+        emit_state(app, state);
+    }
+}
+
+pub fn handle_button(btn: Button, app: &AppHandle, state: &mut T9State) {
+    let (predictive, _) = settings(app);
+
+    match btn {
+        Button::DPadUp => step_focus(state, 0, -1),
+        Button::DPadDown => step_focus(state, 0, 1),
+        Button::DPadLeft => step_focus(state, -1, 0),
+        Button::DPadRight => step_focus(state, 1, 0),
+        Button::South if predictive => press_predictive(app, state),
+        Button::South => press_multi_tap(app, state),
+        Button::West => backspace(app, state),
+        Button::North if predictive && !state.sequence.is_empty() => {
+            let count = candidates(app, state).len().max(1);
+            state.candidate = (state.candidate + 1) % count;
+        }
+        Button::North => {
+            commit_pending(app, state);
+            text_delivery::osk_key(app, "{enter}");
+        }
+        Button::Select => state.shift = !state.shift,
+        Button::East => {
+            commit_pending(app, state);
+            // The word being spelled goes out as its chosen candidate rather than being lost
+            commit_word(app, state);
+            funcs::close_osk(app);
+            return;
+        }
+        _ => return,
+    }
+    emit_state(app, state);
+}
+
+fn press_multi_tap(app: &AppHandle, state: &mut T9State) {
+    let letters = cell_letters(state.focus);
+    if letters.is_empty() {
+        return;
+    }
+
+    // Same key again before the timeout cycles its letters, any other key commits first
+    if let Some(pending) = state.pending.as_mut() {
+        if pending.cell == state.focus {
+            pending.index = (pending.index + 1) % letters.chars().count();
+            pending.since = Instant::now();
+            return;
+        }
+    }
+    commit_pending(app, state);
+
+    if letters == " " {
+        text_delivery::osk_key(app, "{space}");
+        return;
+    }
+    state.pending = Some(Pending { cell: state.focus, index: 0, since: Instant::now() });
+}
+
+fn commit_pending(app: &AppHandle, state: &mut T9State) {
+    let Some(pending) = state.pending.take() else {
+        return;
+    };
+    if let Some(c) = cell_letters(pending.cell).chars().nth(pending.index) {
+        let text: String = if state.shift { c.to_uppercase().collect() } else { c.to_string() };
+        text_delivery::osk_text(app, &text);
+    }
+}
+
+fn press_predictive(app: &AppHandle, state: &mut T9State) {
+    let letters = cell_letters(state.focus);
+    if letters.is_empty() {
+        return;
+    }
+
+    if letters == " " {
+        commit_word(app, state);
+        text_delivery::osk_key(app, "{space}");
+        return;
+    }
+    state.sequence.push(state.focus);
+    state.candidate = 0;
+}
+
+fn commit_word(app: &AppHandle, state: &mut T9State) {
+    let word = candidates(app, state).into_iter().nth(state.candidate);
+    state.sequence.clear();
+    state.candidate = 0;
+    if let Some(word) = word {
+        text_delivery::osk_text(app, &word);
+    }
+}
+
+fn backspace(app: &AppHandle, state: &mut T9State) {
+    if state.pending.take().is_some() {
+        return;
+    }
+    if state.sequence.pop().is_some() {
+        state.candidate = 0;
+        return;
+    }
+    text_delivery::osk_key(app, "{bksp}");
+}
+
+// Words whose letters sit on exactly the pressed keys. Falls back to the first
+// letter of each key so there is always something to commit.
+fn candidates(app: &AppHandle, state: &T9State) -> Vec<String> {
+    if state.sequence.is_empty() {
+        return Vec::new();
+    }
+
+    let sequence = &state.sequence;
+    let mut words = {
+        let state_handle = app.state::<SharedAppState>();
+        let app_state = state_handle.lock().unwrap();
+        app_state.predictor.ranked_words(
+            |w| w.chars().count() == sequence.len() && w.chars().zip(sequence).all(|(c, cell)| cell_for_char(c) == Some(*cell)),
+            CANDIDATE_COUNT,
+        )
+    };
+    if words.is_empty() {
+        words.push(sequence.iter().filter_map(|cell| cell_letters(*cell).chars().next()).collect());
+    }
+    if state.shift {
+        words = words.into_iter().map(|w| w.to_uppercase()).collect();
+    }
+    words
+}
+
+pub fn emit_state(app: &AppHandle, state: &T9State) {
+    let pending = state.pending.as_ref().map(|p| {
+        serde_json::json!({
+            "row": p.cell.0,
+            "col": p.cell.1,
+            "index": p.index,
+            "char": cell_letters(p.cell).chars().nth(p.index),
+        })
+    });
+    let _ = app.emit("osk:t9", serde_json::json!({
+        "cells": CELLS,
+        "focus": { "row": state.focus.0, "col": state.focus.1 },
+        "pending": pending,
+        "candidates": candidates(app, state),
+        "candidate": state.candidate,
+        "shift": state.shift,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_map_to_their_keys() {
+        assert_eq!(cell_for_char('a'), Some((0, 1)));
+        assert_eq!(cell_for_char('S'), Some((2, 0)));
+        assert_eq!(cell_for_char('\''), Some((0, 0)));
+        assert_eq!(cell_for_char('é'), None);
+    }
+
+    #[test]
+    fn bottom_row_only_has_the_middle_key() {
+        let mut state = T9State { focus: (2, 0), ..T9State::default() };
+        step_focus(&mut state, 0, 1);
+        assert_eq!(state.focus, (3, 1));
+        step_focus(&mut state, 1, 0);
+        assert_eq!(state.focus, (3, 1));
+        step_focus(&mut state, 0, -1);
+        assert_eq!(state.focus, (2, 1));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use crate::native_window;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+// A second press within this long confirms a guarded launch
+const CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+
+// Commands that can lose data or end the session always ask first
+const DESTRUCTIVE: &[&str] = &[
+    "rm", "rmdir", "del", "erase", "rd", "format", "mkfs", "dd", "shred", "shutdown", "reboot", "poweroff",
+    "halt", "kill", "killall", "pkill", "taskkill",
+];
+
+// Launch waiting for its second press
+pub struct PendingConfirm {
+    key: String,
+    since: Instant,
+    // The prompt borrowed the OSK window as its overlay and hides it again
+    shown_window: bool,
+}
+
+fn stem(program: &str) -> String {
+    Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+pub fn is_destructive(command_line: &str) -> bool {
+    command_line
+        .split(|c: char| c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')'))
+        .any(|word| DESTRUCTIVE.contains(&stem(word).as_str()))
+}
+
+// True once the launch may go ahead: right away when unguarded, otherwise on the second
+// press. The first press asks through `osk:confirm`, and the question goes away again
+// after `CONFIRM_WINDOW`.
+fn confirmed(app: &AppHandle, key: &str, guarded: bool) -> bool {
+    if !guarded {
+        return true;
+    }
+    let pending = app.state::<SharedAppState>().lock().unwrap().launch_confirm.take();
+    let borrowed = pending.as_ref().is_some_and(|p| p.shown_window);
+    if pending.is_some_and(|p| p.key == key && p.since.elapsed() < CONFIRM_WINDOW) {
+        dismiss(app, borrowed);
+        return true;
+    }
+
+    let shown_window = funcs::show_overlay(app) || borrowed;
+    let since = Instant::now();
+    app.state::<SharedAppState>().lock().unwrap().launch_confirm = Some(PendingConfirm {
+        key: key.to_string(),
+        since,
+        shown_window,
+    });
+    let _ = app.emit("osk:confirm", Some(format!("Press again to run: {}", key)));
+
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(CONFIRM_WINDOW);
+        // Unless it was confirmed or replaced in the meantime
+        let expired = {
+            let state_handle = app.state::<SharedAppState>();
+            let mut state = state_handle.lock().unwrap();
+            match &state.launch_confirm {
+                Some(p) if p.since == since => state.launch_confirm.take(),
+                _ => None,
+            }
+        };
+        if let Some(pending) = expired {
+            dismiss(&app, pending.shown_window);
+        }
+    });
+    false
+}
+
+fn dismiss(app: &AppHandle, shown_window: bool) {
+    let _ = app.emit("osk:confirm", None::<String>);
+    if shown_window {
+        funcs::hide_overlay(app);
+    }
+}
+
+// Brings a window of `program` forward instead of starting a second copy
+fn focus_running(program: &str) -> bool {
+    let wanted = stem(program);
+    native_window::list()
+        .into_iter()
+        .find(|&handle| native_window::process_name(handle).is_some_and(|name| stem(&name) == wanted))
+        .is_some_and(native_window::activate)
+}
+
+pub fn launch(app: &AppHandle, program: &str, args: &[String], cwd: Option<&str>, focus_existing: bool, confirm: bool) {
+    let command_line = std::iter::once(program).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+    if !confirmed(app, &command_line, confirm || is_destructive(&command_line)) {
+        return;
+    }
+    if focus_existing && focus_running(program) {
+        return;
+    }
+
+    let mut command = Command::new(program);
+    command.args(args);
+    spawn(command, cwd);
+}
+
+pub fn run_command(app: &AppHandle, command_line: &str, cwd: Option<&str>, confirm: bool) {
+    if !confirmed(app, command_line, confirm || is_destructive(command_line)) {
+        return;
+    }
+
+    #[cfg(target_os = "windows")]
+    let command = {
+        use std::os::windows::process::CommandExt;
+        // No console window flashing up for every command
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut command = Command::new("cmd");
+        command.arg("/C").raw_arg(command_line).creation_flags(CREATE_NO_WINDOW);
+        command
+    };
+    #[cfg(not(target_os = "windows"))]
+    let command = {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(command_line);
+        command
+    };
+    spawn(command, cwd);
+}
+
+fn spawn(mut command: Command, cwd: Option<&str>) {
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    match command.spawn() {
+        // Reap it whenever it exits
+        Ok(mut child) => {
+            thread::spawn(move || {
+                let _ = child.wait();
+            });
+        }
+        Err(e) => eprintln!("Failed to launch {:?}: {}", command.get_program(), e),
+    }
+}
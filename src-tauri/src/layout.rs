@@ -0,0 +1,177 @@
+use crate::app_state::SharedAppState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use tauri::{AppHandle, Emitter, Manager};
+
+const BUNDLED_LAYOUTS: [&str; 9] = [
+    include_str!("../layouts/qwerty.json"),
+    include_str!("../layouts/azerty.json"),
+    include_str!("../layouts/qwertz.json"),
+    include_str!("../layouts/dvorak.json"),
+    include_str!("../layouts/kana.json"),
+    include_str!("../layouts/pinyin.json"),
+    include_str!("../layouts/numeric.json"),
+    include_str!("../layouts/symbols.json"),
+    include_str!("../layouts/nav.json"),
+];
+const USER_LAYOUTS_DIR: &str = "layouts";
+pub const DEFAULT_LAYOUT: &str = "qwerty";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Layout {
+    pub name: String,
+    pub label: String,
+    // Language of the letters, for accents and the like. Numeric/symbol pages have none.
+    #[serde(default)]
+    pub language: Option<String>,
+    // Keys that combine with the next letter instead of typing, e.g. "^" on AZERTY
+    #[serde(default)]
+    pub dead_keys: Vec<String>,
+    // simple-keyboard layout: layout name ("default", "shift") -> rows of space separated keys
+    pub layout: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Default)]
+pub struct LayoutRegistry {
+    layouts: Vec<Layout>,
+}
+
+impl LayoutRegistry {
+    // Bundled layouts, then *.json from the user's layouts dir. A user layout with a
+    // bundled name replaces it.
+    pub fn load(&mut self, app: &AppHandle) {
+        self.layouts = BUNDLED_LAYOUTS
+            .iter()
+            .map(|json| serde_json::from_str(json).expect("bundled layout is valid"))
+            .collect();
+
+        let Ok(dir) = app.path().app_config_dir().map(|dir| dir.join(USER_LAYOUTS_DIR)) else {
+            return;
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let parsed = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str::<Layout>(&json).map_err(|e| e.to_string()));
+            match parsed {
+                Ok(layout) => match self.layouts.iter_mut().find(|l| l.name == layout.name) {
+                    Some(existing) => *existing = layout,
+                    None => self.layouts.push(layout),
+                },
+                Err(e) => eprintln!("Failed to load layout {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    pub fn all(&self) -> &[Layout] {
+        &self.layouts
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Layout> {
+        self.layouts.iter().find(|l| l.name == name)
+    }
+
+    // Unknown names (e.g. a deleted user layout) fall back to the default, then the first
+    pub fn get_or_default(&self, name: &str) -> Option<&Layout> {
+        self.get(name).or_else(|| self.get(DEFAULT_LAYOUT)).or_else(|| self.layouts.first())
+    }
+
+    // Name of the layout `step` places after `name`, wrapping around
+    pub fn cycle(&self, name: &str, step: i32) -> Option<&str> {
+        if self.layouts.is_empty() {
+            return None;
+        }
+        let len = self.layouts.len() as i32;
+        let index = self.layouts.iter().position(|l| l.name == name).unwrap_or(0) as i32;
+        Some(&self.layouts[(index + step).rem_euclid(len) as usize].name)
+    }
+}
+
+// Switches the active profile to `name` and tells the frontend. Returns false for
+// unknown layouts.
+pub fn select(app: &AppHandle, name: &str) -> bool {
+    let profiles = {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        if state.layouts.get(name).is_none() {
+            return false;
+        }
+        state.profiles.active_mut().layout = name.to_string();
+        state.profiles.clone()
+    };
+    profiles.save(app);
+    emit_layout(app);
+    true
+}
+
+pub fn cycle(app: &AppHandle, step: i32) {
+    let next = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.layouts.cycle(&state.profiles.active().layout, step).map(str::to_string)
+    };
+    if let Some(next) = next {
+        select(app, &next);
+    }
+}
+
+// The active profile's layout, for the frontend to hand to simple-keyboard
+pub fn emit_layout(app: &AppHandle) {
+    let layout = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.layouts.get_or_default(&state.profiles.active().layout).cloned()
+    };
+    if let Some(layout) = layout {
+        let _ = app.emit("osk:layout", layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled() -> LayoutRegistry {
+        LayoutRegistry {
+            layouts: BUNDLED_LAYOUTS.iter().map(|json| serde_json::from_str(json).unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn cycling_wraps_both_ways() {
+        let registry = bundled();
+        assert_eq!(registry.cycle("qwerty", 1), Some("azerty"));
+        assert_eq!(registry.cycle("qwerty", -1), Some("nav"));
+        assert_eq!(registry.cycle("nav", 1), Some("qwerty"));
+        assert_eq!(registry.cycle("azerty", 9), Some("azerty"));
+    }
+
+    #[test]
+    fn cycling_from_an_unknown_layout_starts_at_the_first() {
+        assert_eq!(bundled().cycle("deleted", 1), Some("azerty"));
+        assert_eq!(LayoutRegistry::default().cycle("qwerty", 1), None);
+    }
+
+    #[test]
+    fn unknown_names_fall_back_to_the_default() {
+        let registry = bundled();
+        assert_eq!(registry.get_or_default("deleted").map(|l| l.name.as_str()), Some(DEFAULT_LAYOUT));
+        assert_eq!(registry.get_or_default("dvorak").map(|l| l.name.as_str()), Some("dvorak"));
+    }
+
+    #[test]
+    fn bundled_layouts_have_a_default_layer() {
+        for layout in bundled().all() {
+            assert!(layout.layout.contains_key("default"), "{} has no default layer", layout.name);
+        }
+    }
+}
@@ -1,10 +1,29 @@
+mod actions;
+mod alternates;
 mod app_state;
+mod clipboard_history;
 mod commands;
+mod compose;
 mod funcs;
 mod gamepad;
+mod hotspots;
+mod ime;
+#[cfg(target_os = "windows")]
+mod injection;
 mod input_mapper;
+mod launcher;
+mod layout;
+mod monitor;
+mod native_window;
+mod overlay;
+mod prediction;
+mod profile;
 mod setup;
+mod staging;
+mod text_delivery;
 mod tray;
+mod window_manager;
+mod window_switcher;
 
 use app_state::AppState;
 use std::sync::Mutex;
@@ -15,9 +34,33 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(Mutex::new(AppState::default()))
         .setup(setup::init)
-        .invoke_handler(tauri::generate_handler![commands::greet, commands::send_key])
+        .invoke_handler(tauri::generate_handler![
+            commands::greet,
+            commands::send_key,
+            commands::send_text,
+            commands::accept_suggestion,
+            commands::set_prediction_incognito,
+            commands::set_key_geometry,
+            commands::get_profile,
+            commands::list_profiles,
+            commands::select_profile,
+            commands::save_profile,
+            commands::set_osk_mode,
+            commands::list_layouts,
+            commands::select_layout,
+            commands::set_staging,
+            commands::staging_action,
+            commands::list_clips,
+            commands::paste_clip,
+            commands::pin_clip,
+            commands::delete_clip,
+            commands::clear_clips,
+            commands::list_windows,
+            commands::activate_window
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
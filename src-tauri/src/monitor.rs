@@ -0,0 +1,217 @@
+use enigo::{Coordinate, Enigo, Mouse};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+// (x, y, width, height) in physical pixels
+pub type Rect = (i32, i32, i32, i32);
+
+// Stick speed is tuned on a 1080p panel at 100% scaling
+const REFERENCE_HEIGHT: f32 = 1080.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedScaling {
+    // Same pixels per tick everywhere
+    Off,
+    // Follows the OS scale factor
+    Dpi,
+    // Crossing a monitor takes as long on a 4K TV as on a 1080p panel
+    #[default]
+    Resolution,
+}
+
+// What happens when the cursor hits the edge of the desktop
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorEdge {
+    #[default]
+    Stop,
+    // Comes back in on the far side
+    Wrap,
+    // Can't leave the monitor it is on; jumping to the next one still works
+    Confine,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MonitorInfo {
+    pub rect: Rect,
+    // The part left over by taskbars and panels
+    pub work_area: Rect,
+    pub scale_factor: f64,
+}
+
+impl MonitorInfo {
+    pub fn speed(&self, scaling: SpeedScaling) -> f32 {
+        match scaling {
+            SpeedScaling::Off => 1.0,
+            SpeedScaling::Dpi => self.scale_factor as f32,
+            SpeedScaling::Resolution => self.rect.3 as f32 / REFERENCE_HEIGHT,
+        }
+    }
+}
+
+// Every monitor, left to right
+pub fn list(app: &AppHandle) -> Vec<MonitorInfo> {
+    let mut monitors: Vec<MonitorInfo> = app
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            let area = m.work_area();
+            MonitorInfo {
+                rect: (m.position().x, m.position().y, m.size().width as i32, m.size().height as i32),
+                work_area: (area.position.x, area.position.y, area.size.width as i32, area.size.height as i32),
+                scale_factor: m.scale_factor(),
+            }
+        })
+        .collect();
+    monitors.sort_by_key(|m| m.rect);
+    monitors
+}
+
+// Full bounds of every monitor, left to right
+pub fn bounds(app: &AppHandle) -> Vec<Rect> {
+    list(app).iter().map(|m| m.rect).collect()
+}
+
+pub fn contains(&(x, y, w, h): &Rect, px: i32, py: i32) -> bool {
+    px >= x && px < x + w && py >= y && py < y + h
+}
+
+// Monitor holding the point, the first one if it is off every screen
+pub fn at(app: &AppHandle, x: i32, y: i32) -> Option<Rect> {
+    let rects = bounds(app);
+    rects.iter().find(|r| contains(r, x, y)).or(rects.first()).copied()
+}
+
+pub fn centre(&(x, y, w, h): &Rect) -> (i32, i32) {
+    (x + w / 2, y + h / 2)
+}
+
+// Identifies the current arrangement, so positions saved on one setup aren't replayed on another
+pub fn layout_key(app: &AppHandle) -> String {
+    bounds(app)
+        .iter()
+        .map(|(x, y, w, h)| format!("{}x{}@{},{}", w, h, x, y))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+// Index of the monitor holding the point, the first one if it is off every screen
+pub fn index_at(monitors: &[MonitorInfo], x: i32, y: i32) -> usize {
+    monitors.iter().position(|m| contains(&m.rect, x, y)).unwrap_or(0)
+}
+
+// Where the cursor ends up after moving from `from` to `to` under the edge rule
+pub fn constrain(monitors: &[MonitorInfo], edge: CursorEdge, from: (i32, i32), to: (i32, i32)) -> (i32, i32) {
+    let Some(current) = monitors.get(index_at(monitors, from.0, from.1)) else {
+        return to;
+    };
+    let (x, y, w, h) = current.rect;
+    match edge {
+        CursorEdge::Stop => to,
+        CursorEdge::Confine => (to.0.clamp(x, x + w - 1), to.1.clamp(y, y + h - 1)),
+        CursorEdge::Wrap => {
+            if monitors.iter().any(|m| contains(&m.rect, to.0, to.1)) {
+                return to;
+            }
+            // Off the side of the desktop: in again on the monitor at the other end, at the
+            // same height. Off the top or bottom: wrap within this monitor.
+            let fraction = (to.1 - y) as f32 / h as f32;
+            let opposite = if to.0 >= x + w {
+                monitors.first()
+            } else if to.0 < x {
+                monitors.last()
+            } else {
+                None
+            };
+            match opposite {
+                Some(m) => {
+                    let (ox, oy, ow, oh) = m.rect;
+                    let wrapped_y = oy + (fraction.clamp(0.0, 1.0) * (oh - 1) as f32) as i32;
+                    (if to.0 < x { ox + ow - 1 } else { ox }, wrapped_y)
+                }
+                None => (to.0, if to.1 < y { y + h - 1 } else { y }),
+            }
+        }
+    }
+}
+
+// Puts the cursor in the middle of the monitor after the one it is on, wrapping around
+pub fn jump_to_next(app: &AppHandle, enigo: &mut Enigo) {
+    let monitors = list(app);
+    if monitors.is_empty() {
+        return;
+    }
+    let (x, y) = enigo.location().unwrap_or((0, 0));
+    let next = (index_at(&monitors, x, y) + 1) % monitors.len();
+    let (cx, cy) = centre(&monitors[next].rect);
+    let _ = enigo.move_mouse(cx, cy, Coordinate::Abs);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Two 1080p panels side by side
+    pub(crate) fn monitors() -> Vec<MonitorInfo> {
+        [(0, 0, 1920, 1080), (1920, 0, 1920, 1080)]
+            .into_iter()
+            .map(|rect| MonitorInfo {
+                rect,
+                work_area: rect,
+                scale_factor: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stop_lets_the_move_through() {
+        assert_eq!(constrain(&monitors(), CursorEdge::Stop, (10, 10), (-50, 10)), (-50, 10));
+    }
+
+    #[test]
+    fn confine_keeps_the_cursor_on_its_monitor() {
+        let monitors = monitors();
+        assert_eq!(constrain(&monitors, CursorEdge::Confine, (1900, 500), (1950, 500)), (1919, 500));
+        assert_eq!(constrain(&monitors, CursorEdge::Confine, (2000, 10), (2000, -20)), (2000, 0));
+    }
+
+    #[test]
+    fn wrap_crosses_between_monitors_normally() {
+        assert_eq!(constrain(&monitors(), CursorEdge::Wrap, (1900, 500), (1950, 500)), (1950, 500));
+    }
+
+    #[test]
+    fn wrap_off_the_sides_comes_in_at_the_far_end() {
+        let monitors = monitors();
+        assert_eq!(constrain(&monitors, CursorEdge::Wrap, (3830, 540), (3850, 540)), (0, 539));
+        assert_eq!(constrain(&monitors, CursorEdge::Wrap, (5, 540), (-10, 540)), (3839, 539));
+    }
+
+    #[test]
+    fn wrap_off_the_top_or_bottom_stays_on_the_monitor() {
+        let monitors = monitors();
+        assert_eq!(constrain(&monitors, CursorEdge::Wrap, (100, 5), (100, -10)), (100, 1079));
+        assert_eq!(constrain(&monitors, CursorEdge::Wrap, (2000, 1075), (2000, 1090)), (2000, 0));
+    }
+
+    #[test]
+    fn off_screen_points_belong_to_the_first_monitor() {
+        let monitors = monitors();
+        assert_eq!(index_at(&monitors, 1920, 0), 1);
+        assert_eq!(index_at(&monitors, -500, -500), 0);
+    }
+
+    #[test]
+    fn speed_follows_the_resolution_by_default() {
+        let monitor = MonitorInfo {
+            rect: (0, 0, 3840, 2160),
+            work_area: (0, 0, 3840, 2160),
+            scale_factor: 1.5,
+        };
+        assert_eq!(monitor.speed(SpeedScaling::default()), 2.0);
+        assert_eq!(monitor.speed(SpeedScaling::Dpi), 1.5);
+        assert_eq!(monitor.speed(SpeedScaling::Off), 1.0);
+    }
+}
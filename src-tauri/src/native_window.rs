@@ -0,0 +1,553 @@
+use tauri::{AppHandle, Manager};
+
+// Window handles are passed around as `isize` (see `AppState::target_hwnd`):
+// an HWND on Windows, an X11 window id on Linux. 0 means "no window".
+
+#[cfg(target_os = "windows")]
+pub fn foreground() -> isize {
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+    unsafe { GetForegroundWindow().0 as isize }
+}
+
+#[cfg(target_os = "windows")]
+pub fn exists(handle: isize) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+    handle != 0 && unsafe { IsWindow(Some(HWND(handle as _))).as_bool() }
+}
+
+#[cfg(target_os = "windows")]
+pub fn activate(handle: isize) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{IsIconic, SetForegroundWindow, ShowWindow, SW_RESTORE};
+    if !exists(handle) {
+        return false;
+    }
+    let hwnd = HWND(handle as _);
+    unsafe {
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        SetForegroundWindow(hwnd).as_bool()
+    }
+}
+
+// Executable name without extension, e.g. "KeePassXC"
+#[cfg(target_os = "windows")]
+pub fn process_name(handle: isize) -> Option<String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::{CloseHandle, HWND};
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    if !exists(handle) {
+        return None;
+    }
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(HWND(handle as _), Some(&mut pid)) };
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    let mut buf = [0u16; 260];
+    let mut len = buf.len() as u32;
+    let queried = unsafe { QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buf.as_mut_ptr()), &mut len) };
+    unsafe {
+        let _ = CloseHandle(process);
+    }
+    queried.ok()?;
+    let path = String::from_utf16_lossy(&buf[..len as usize]);
+    std::path::Path::new(&path).file_stem().map(|s| s.to_string_lossy().into_owned())
+}
+
+// Window that put the current contents on the clipboard, 0 if unknown
+#[cfg(target_os = "windows")]
+pub fn clipboard_owner() -> isize {
+    use windows::Win32::System::DataExchange::GetClipboardOwner;
+    unsafe { GetClipboardOwner() }.map(|h| h.0 as isize).unwrap_or(0)
+}
+
+// The copying app asked for its data to stay out of clipboard monitors and history
+// (password managers do this)
+#[cfg(target_os = "windows")]
+pub fn clipboard_private() -> bool {
+    use windows::core::w;
+    use windows::Win32::Foundation::HGLOBAL;
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard, RegisterClipboardFormatW,
+    };
+    use windows::Win32::System::Memory::{GlobalLock, GlobalUnlock};
+
+    let exclude = unsafe { RegisterClipboardFormatW(w!("ExcludeClipboardContentFromMonitorProcessing")) };
+    if exclude != 0 && unsafe { IsClipboardFormatAvailable(exclude) }.is_ok() {
+        return true;
+    }
+    // A DWORD, 0 means "keep out of history"
+    let can_include = unsafe { RegisterClipboardFormatW(w!("CanIncludeInClipboardHistory")) };
+    if can_include == 0 || unsafe { IsClipboardFormatAvailable(can_include) }.is_err() {
+        return false;
+    }
+    // Err on the side of not recording when the value can't be read
+    if unsafe { OpenClipboard(None) }.is_err() {
+        return true;
+    }
+    let allowed = unsafe {
+        GetClipboardData(can_include).ok().and_then(|handle| {
+            let memory = HGLOBAL(handle.0);
+            let value = GlobalLock(memory) as *const u32;
+            let allowed = (!value.is_null()).then(|| *value != 0);
+            let _ = GlobalUnlock(memory);
+            allowed
+        })
+    };
+    unsafe {
+        let _ = CloseClipboard();
+    }
+    !allowed.unwrap_or(false)
+}
+
+#[cfg(target_os = "windows")]
+pub fn title(handle: isize) -> Option<String> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{GetWindowTextLengthW, GetWindowTextW};
+
+    let hwnd = HWND(handle as _);
+    let len = unsafe { GetWindowTextLengthW(hwnd) };
+    if len <= 0 {
+        return None;
+    }
+    let mut buf = vec![0u16; len as usize + 1];
+    let copied = unsafe { GetWindowTextW(hwnd, &mut buf) };
+    Some(String::from_utf16_lossy(&buf[..copied.max(0) as usize]))
+}
+
+// Top-level windows a taskbar would show, front to back
+#[cfg(target_os = "windows")]
+pub fn list() -> Vec<isize> {
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{HWND, LPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindow, GetWindowLongPtrW, IsWindowVisible, GWL_EXSTYLE, GW_OWNER, WS_EX_TOOLWINDOW,
+    };
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let handles = unsafe { &mut *(lparam.0 as *mut Vec<isize>) };
+        let visible = unsafe { IsWindowVisible(hwnd).as_bool() };
+        let owned = unsafe { GetWindow(hwnd, GW_OWNER) }.is_ok();
+        let tool = unsafe { GetWindowLongPtrW(hwnd, GWL_EXSTYLE) } & WS_EX_TOOLWINDOW.0 as isize != 0;
+        if visible && !owned && !tool {
+            handles.push(hwnd.0 as isize);
+        }
+        true.into()
+    }
+
+    let mut handles: Vec<isize> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut handles as *mut Vec<isize> as isize));
+    }
+    handles
+}
+
+// Outer bounds as (x, y, width, height) in physical pixels
+#[cfg(target_os = "windows")]
+pub fn rect(handle: isize) -> Option<(i32, i32, i32, i32)> {
+    use windows::Win32::Foundation::{HWND, RECT};
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+    if !exists(handle) {
+        return None;
+    }
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(HWND(handle as _), &mut rect) }.ok()?;
+    Some((rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top))
+}
+
+// Un-maximizes first, a maximized window would ignore the new bounds
+#[cfg(target_os = "windows")]
+pub fn set_rect(handle: isize, x: i32, y: i32, width: i32, height: i32) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        IsZoomed, SetWindowPos, ShowWindow, SWP_NOACTIVATE, SWP_NOZORDER, SW_RESTORE,
+    };
+
+    if !exists(handle) {
+        return false;
+    }
+    let hwnd = HWND(handle as _);
+    unsafe {
+        if IsZoomed(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        SetWindowPos(hwnd, None, x, y, width, height, SWP_NOZORDER | SWP_NOACTIVATE).is_ok()
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn minimize(handle: isize) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_MINIMIZE};
+
+    exists(handle) && unsafe { ShowWindow(HWND(handle as _), SW_MINIMIZE) }.as_bool()
+}
+
+#[cfg(target_os = "windows")]
+pub fn toggle_maximize(handle: isize) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{IsZoomed, ShowWindow, SW_MAXIMIZE, SW_RESTORE};
+
+    if !exists(handle) {
+        return false;
+    }
+    let hwnd = HWND(handle as _);
+    unsafe {
+        let cmd = if IsZoomed(hwnd).as_bool() { SW_RESTORE } else { SW_MAXIMIZE };
+        let _ = ShowWindow(hwnd, cmd);
+    }
+    true
+}
+
+// Asks nicely, the app may still prompt to save
+#[cfg(target_os = "windows")]
+pub fn close(handle: isize) -> bool {
+    use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{PostMessageW, WM_CLOSE};
+
+    exists(handle) && unsafe { PostMessageW(Some(HWND(handle as _)), WM_CLOSE, WPARAM(0), LPARAM(0)) }.is_ok()
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use std::sync::OnceLock;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{Atom, AtomEnum, ClientMessageEvent, ConnectionExt, EventMask, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    pub struct X11 {
+        pub conn: RustConnection,
+        pub root: Window,
+        pub net_active_window: Atom,
+    }
+
+    static X11_CONN: OnceLock<Option<X11>> = OnceLock::new();
+
+    // Connects once and caches the result; None on Wayland-only sessions.
+    pub fn get() -> Option<&'static X11> {
+        X11_CONN
+            .get_or_init(|| {
+                let (conn, screen_num) = x11rb::connect(None).ok()?;
+                let root = conn.setup().roots.get(screen_num)?.root;
+                let net_active_window = intern(&conn, "_NET_ACTIVE_WINDOW")?;
+                Some(X11 { conn, root, net_active_window })
+            })
+            .as_ref()
+    }
+
+    pub fn intern(conn: &RustConnection, name: &str) -> Option<Atom> {
+        Some(conn.intern_atom(false, name.as_bytes()).ok()?.reply().ok()?.atom)
+    }
+
+    pub fn active_window(x: &X11) -> Option<Window> {
+        let reply = x
+            .conn
+            .get_property(false, x.root, x.net_active_window, AtomEnum::WINDOW, 0, 1)
+            .ok()?
+            .reply()
+            .ok()?;
+        let window = reply.value32()?.next()?;
+        (window != 0).then_some(window)
+    }
+
+    pub fn window_pid(x: &X11, window: Window) -> Option<u32> {
+        let net_wm_pid = intern(&x.conn, "_NET_WM_PID")?;
+        let reply = x
+            .conn
+            .get_property(false, window, net_wm_pid, AtomEnum::CARDINAL, 0, 1)
+            .ok()?
+            .reply()
+            .ok()?;
+        reply.value32().and_then(|mut values| values.next())
+    }
+
+    pub fn selection_owner(x: &X11, selection: &str) -> Option<Window> {
+        let atom = intern(&x.conn, selection)?;
+        let owner = x.conn.get_selection_owner(atom).ok()?.reply().ok()?.owner;
+        (owner != 0).then_some(owner)
+    }
+
+    pub fn window_title(x: &X11, window: Window) -> Option<String> {
+        let net_wm_name = intern(&x.conn, "_NET_WM_NAME")?;
+        let utf8_string = intern(&x.conn, "UTF8_STRING")?;
+        let property = |name: Atom, kind: Atom| {
+            let reply = x.conn.get_property(false, window, name, kind, 0, 1024).ok()?.reply().ok()?;
+            (!reply.value.is_empty()).then(|| String::from_utf8_lossy(&reply.value).into_owned())
+        };
+        // Old clients only set the Latin-1 WM_NAME
+        property(net_wm_name, utf8_string).or_else(|| property(AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()))
+    }
+
+    // Managed windows, front to back when the WM keeps the stacking list
+    pub fn client_list(x: &X11) -> Vec<Window> {
+        let read = |name: &str| -> Option<Vec<Window>> {
+            let atom = intern(&x.conn, name)?;
+            let reply = x
+                .conn
+                .get_property(false, x.root, atom, AtomEnum::WINDOW, 0, 1024)
+                .ok()?
+                .reply()
+                .ok()?;
+            let windows: Vec<Window> = reply.value32()?.collect();
+            Some(windows)
+        };
+        match read("_NET_CLIENT_LIST_STACKING") {
+            Some(mut stacking) => {
+                stacking.reverse();
+                stacking
+            }
+            None => read("_NET_CLIENT_LIST").unwrap_or_default(),
+        }
+    }
+
+    // EWMH request to the window manager about `window`, sent to the root window
+    pub fn client_message(x: &X11, window: Window, kind: &str, data: [u32; 5]) -> bool {
+        let Some(kind) = intern(&x.conn, kind) else {
+            return false;
+        };
+        let event = ClientMessageEvent::new(32, window, kind, data);
+        let sent = x
+            .conn
+            .send_event(
+                false,
+                x.root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )
+            .is_ok();
+        sent && x.conn.flush().is_ok()
+    }
+
+    pub fn window_rect(x: &X11, window: Window) -> Option<(i32, i32, i32, i32)> {
+        let geometry = x.conn.get_geometry(window).ok()?.reply().ok()?;
+        let origin = x.conn.translate_coordinates(window, x.root, 0, 0).ok()?.reply().ok()?;
+        Some((
+            origin.dst_x as i32,
+            origin.dst_y as i32,
+            geometry.width as i32,
+            geometry.height as i32,
+        ))
+    }
+
+    // _NET_WM_STATE action: 0 remove, 1 add, 2 toggle
+    pub fn set_maximized(x: &X11, window: Window, action: u32) -> bool {
+        let (Some(vert), Some(horz)) = (
+            intern(&x.conn, "_NET_WM_STATE_MAXIMIZED_VERT"),
+            intern(&x.conn, "_NET_WM_STATE_MAXIMIZED_HORZ"),
+        ) else {
+            return false;
+        };
+        client_message(x, window, "_NET_WM_STATE", [action, vert, horz, 2, 0])
+    }
+
+    pub fn window_exists(x: &X11, window: Window) -> bool {
+        match x.conn.get_window_attributes(window) {
+            Ok(cookie) => cookie.reply().is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    pub fn activate_window(x: &X11, window: Window) -> bool {
+        // EWMH: source indication 2 = pager, so the WM honours the request.
+        let event = ClientMessageEvent::new(32, window, x.net_active_window, [2, 0, 0, 0, 0]);
+        let sent = x
+            .conn
+            .send_event(
+                false,
+                x.root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )
+            .is_ok();
+        sent && x.conn.flush().is_ok()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn foreground() -> isize {
+    x11::get().and_then(x11::active_window).map(|w| w as isize).unwrap_or(0)
+}
+
+#[cfg(target_os = "linux")]
+pub fn exists(handle: isize) -> bool {
+    handle != 0 && x11::get().is_some_and(|x| x11::window_exists(x, handle as u32))
+}
+
+#[cfg(target_os = "linux")]
+pub fn activate(handle: isize) -> bool {
+    if !exists(handle) {
+        return false;
+    }
+    x11::get().is_some_and(|x| x11::activate_window(x, handle as u32))
+}
+
+#[cfg(target_os = "linux")]
+pub fn process_name(handle: isize) -> Option<String> {
+    if !exists(handle) {
+        return None;
+    }
+    let pid = x11::get().and_then(|x| x11::window_pid(x, handle as u32))?;
+    // comm is cut at 15 characters, so it only stands in when the executable can't be read
+    // (another user's process)
+    let exe = std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|path| path.to_string_lossy().trim_end_matches(" (deleted)").to_string())
+        .or_else(|| {
+            let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+            let argv0 = cmdline.split(|&b| b == 0).next().filter(|a| !a.is_empty())?;
+            Some(String::from_utf8_lossy(argv0).into_owned())
+        });
+    match exe {
+        Some(exe) => std::path::Path::new(&exe).file_name().map(|s| s.to_string_lossy().into_owned()),
+        None => Some(std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?.trim().to_string()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn clipboard_owner() -> isize {
+    x11::get().and_then(|x| x11::selection_owner(x, "CLIPBOARD")).map(|w| w as isize).unwrap_or(0)
+}
+
+// X11 has no convention for marking clipboard data as sensitive
+#[cfg(target_os = "linux")]
+pub fn clipboard_private() -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+pub fn title(handle: isize) -> Option<String> {
+    x11::get().and_then(|x| x11::window_title(x, handle as u32))
+}
+
+#[cfg(target_os = "linux")]
+pub fn list() -> Vec<isize> {
+    x11::get().map(|x| x11::client_list(x).into_iter().map(|w| w as isize).collect()).unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+pub fn rect(handle: isize) -> Option<(i32, i32, i32, i32)> {
+    if !exists(handle) {
+        return None;
+    }
+    x11::get().and_then(|x| x11::window_rect(x, handle as u32))
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_rect(handle: isize, x: i32, y: i32, width: i32, height: i32) -> bool {
+    if !exists(handle) {
+        return false;
+    }
+    let Some(conn) = x11::get() else {
+        return false;
+    };
+    x11::set_maximized(conn, handle as u32, 0);
+    // Flags: static gravity, x/y/width/height present, source indication 2 (pager)
+    let flags = 10 | (0b1111 << 8) | (2 << 12);
+    let data = [flags, x as u32, y as u32, width.max(1) as u32, height.max(1) as u32];
+    x11::client_message(conn, handle as u32, "_NET_MOVERESIZE_WINDOW", data)
+}
+
+#[cfg(target_os = "linux")]
+pub fn minimize(handle: isize) -> bool {
+    // ICCCM IconicState
+    exists(handle) && x11::get().is_some_and(|x| x11::client_message(x, handle as u32, "WM_CHANGE_STATE", [3, 0, 0, 0, 0]))
+}
+
+#[cfg(target_os = "linux")]
+pub fn toggle_maximize(handle: isize) -> bool {
+    exists(handle) && x11::get().is_some_and(|x| x11::set_maximized(x, handle as u32, 2))
+}
+
+#[cfg(target_os = "linux")]
+pub fn close(handle: isize) -> bool {
+    exists(handle) && x11::get().is_some_and(|x| x11::client_message(x, handle as u32, "_NET_CLOSE_WINDOW", [0, 2, 0, 0, 0]))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn foreground() -> isize {
+    0
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn exists(_handle: isize) -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn activate(_handle: isize) -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn process_name(_handle: isize) -> Option<String> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn clipboard_owner() -> isize {
+    0
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn clipboard_private() -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn title(_handle: isize) -> Option<String> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn list() -> Vec<isize> {
+    Vec::new()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn rect(_handle: isize) -> Option<(i32, i32, i32, i32)> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn set_rect(_handle: isize, _x: i32, _y: i32, _width: i32, _height: i32) -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn minimize(_handle: isize) -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn toggle_maximize(_handle: isize) -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn close(_handle: isize) -> bool {
+    false
+}
+
+// Handle of our own OSK window, so it is never picked as a typing target.
+pub fn own_handle(app: &AppHandle) -> isize {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let Some(window) = app.get_webview_window("main") else {
+        return 0;
+    };
+    let Ok(handle) = window.window_handle() else {
+        return 0;
+    };
+    match handle.as_raw() {
+        RawWindowHandle::Win32(h) => h.hwnd.get(),
+        RawWindowHandle::Xlib(h) => h.window as isize,
+        RawWindowHandle::Xcb(h) => h.window.get() as isize,
+        _ => 0,
+    }
+}
@@ -0,0 +1,53 @@
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize};
+
+// Transparent, click-through window covering a whole monitor, for markers the OSK
+// window is too small to draw (e.g. the cursor jump grid)
+const LABEL: &str = "overlay";
+
+pub fn init(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(LABEL) else {
+        return;
+    };
+    let _ = window.set_ignore_cursor_events(true);
+
+    // Never take focus from the window the user is working in
+    #[cfg(target_os = "windows")]
+    {
+        use raw_window_handle::HasWindowHandle;
+        if let Ok(handle) = window.window_handle() {
+            if let raw_window_handle::RawWindowHandle::Win32(win32_handle) = handle.as_raw() {
+                use windows::Win32::Foundation::HWND;
+                use windows::Win32::UI::WindowsAndMessaging::{
+                    GetWindowLongPtrW, SetWindowLongPtrW, GWL_EXSTYLE, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW,
+                };
+
+                let hwnd = HWND(win32_handle.hwnd.get() as _);
+                unsafe {
+                    let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+                    let new_style = ex_style | (WS_EX_NOACTIVATE.0 as isize) | (WS_EX_TOOLWINDOW.0 as isize);
+                    SetWindowLongPtrW(hwnd, GWL_EXSTYLE, new_style);
+                }
+            }
+        }
+    }
+}
+
+// Covers the monitor at (x, y, width, height), in physical pixels
+pub fn show(app: &AppHandle, (x, y, width, height): (i32, i32, i32, i32)) {
+    let Some(window) = app.get_webview_window(LABEL) else {
+        return;
+    };
+    let _ = window.set_position(PhysicalPosition { x, y });
+    let _ = window.set_size(PhysicalSize {
+        width: width.max(1) as u32,
+        height: height.max(1) as u32,
+    });
+    let _ = window.show();
+    let _ = window.set_always_on_top(true);
+}
+
+pub fn hide(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.hide();
+    }
+}
@@ -0,0 +1,272 @@
+use crate::app_state::SharedAppState;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Most frequent words first, one per line
+const BUNDLED_DICTIONARY: &str = include_str!("../dictionaries/en.txt");
+const USER_DICTIONARY_FILE: &str = "user_words.json";
+const SUGGESTION_COUNT: usize = 3;
+const MIN_LEARN_LEN: usize = 2;
+
+pub struct Predictor {
+    // Bundled word -> rank (0 = most frequent)
+    base: HashMap<String, usize>,
+    base_order: Vec<String>,
+    // Learned word -> times typed
+    user: HashMap<String, u32>,
+    current: String,
+    pub incognito: bool,
+    dirty: bool,
+}
+
+impl Default for Predictor {
+    fn default() -> Self {
+        let base_order: Vec<String> = BUNDLED_DICTIONARY
+            .lines()
+            .map(|l| l.trim().to_lowercase())
+            .filter(|l| !l.is_empty())
+            .collect();
+        let base = base_order.iter().enumerate().map(|(i, w)| (w.clone(), i)).collect();
+        Self {
+            base,
+            base_order,
+            user: HashMap::new(),
+            current: String::new(),
+            incognito: false,
+            dirty: false,
+        }
+    }
+}
+
+impl Predictor {
+    pub fn load_user_dictionary(&mut self, app: &AppHandle) {
+        let Some(path) = user_dictionary_path(app) else {
+            return;
+        };
+        if let Ok(json) = fs::read_to_string(&path) {
+            match serde_json::from_str(&json) {
+                Ok(user) => self.user = user,
+                Err(e) => eprintln!("Failed to parse {}: {}", USER_DICTIONARY_FILE, e),
+            }
+        }
+    }
+
+    pub fn save_user_dictionary(&mut self, app: &AppHandle) {
+        if !self.dirty {
+            return;
+        }
+        let Some(path) = user_dictionary_path(app) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        match serde_json::to_string(&self.user) {
+            Ok(json) => {
+                if let Err(e) = fs::write(&path, json) {
+                    eprintln!("Failed to save {}: {}", USER_DICTIONARY_FILE, e);
+                } else {
+                    self.dirty = false;
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize user dictionary: {}", e),
+        }
+    }
+
+    pub fn current_word(&self) -> &str {
+        &self.current
+    }
+
+    // Feed typed text; anything that isn't part of a word ends the current one
+    pub fn push_text(&mut self, text: &str) {
+        for c in text.chars() {
+            if is_word_char(c) {
+                self.current.push(c);
+            } else {
+                self.commit_word();
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.current.pop();
+    }
+
+    // The caret moved somewhere we can't follow, forget the partial word
+    pub fn reset(&mut self) {
+        self.current.clear();
+    }
+
+    pub fn commit_word(&mut self) {
+        let word = std::mem::take(&mut self.current);
+        self.learn(&word);
+    }
+
+    pub fn learn(&mut self, word: &str) {
+        if self.incognito || word.chars().count() < MIN_LEARN_LEN {
+            return;
+        }
+        *self.user.entry(word.to_lowercase()).or_insert(0) += 1;
+        self.dirty = true;
+    }
+
+    pub fn suggestions(&self) -> Vec<String> {
+        if self.current.is_empty() {
+            return Vec::new();
+        }
+        let prefix = self.current.to_lowercase();
+        let capitalize = self.current.chars().next().is_some_and(char::is_uppercase);
+
+        self.ranked_words(|w| w.starts_with(&prefix) && w != prefix, SUGGESTION_COUNT)
+            .into_iter()
+            .map(|w| if capitalize { capitalized(&w) } else { w })
+            .collect()
+    }
+
+    // Known words passing `filter`. Learned words outrank bundled ones; within each, more frequent first.
+    pub fn ranked_words(&self, filter: impl Fn(&str) -> bool, limit: usize) -> Vec<String> {
+        let mut learned: Vec<(&String, u32)> = self
+            .user
+            .iter()
+            .filter(|(w, _)| filter(w))
+            .map(|(w, n)| (w, *n))
+            .collect();
+        learned.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| self.rank(a.0).cmp(&self.rank(b.0)))
+                .then_with(|| a.0.cmp(b.0))
+        });
+
+        let bundled = self
+            .base_order
+            .iter()
+            .filter(|w| filter(w) && !self.user.contains_key(*w));
+
+        learned
+            .into_iter()
+            .map(|(w, _)| w)
+            .chain(bundled)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    // Backspaces and characters needed to turn the current word into `word`: just the
+    // rest of it when it completes what was typed, the whole word otherwise
+    pub fn replacement_for(&self, word: &str) -> (usize, String) {
+        let typed = self.current.chars().count();
+        if word.to_lowercase().starts_with(&self.current.to_lowercase()) {
+            (0, word.chars().skip(typed).collect())
+        } else {
+            (typed, word.to_string())
+        }
+    }
+
+    fn rank(&self, word: &str) -> usize {
+        self.base.get(word).copied().unwrap_or(usize::MAX)
+    }
+}
+
+// Follow a key sent through `send_key` so the predictor knows the word being typed
+pub fn observe_key(app: &AppHandle, key: Option<&str>, text: Option<&str>) {
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let predictor = &mut state.predictor;
+        match (key, text) {
+            (_, Some(text)) => predictor.push_text(text),
+            (Some("{bksp}"), _) => predictor.backspace(),
+            (Some("{space}"), _) | (Some("{enter}"), _) | (Some("{tab}"), _) => predictor.commit_word(),
+            (Some("{shift}"), _) | (Some("{lock}"), _) => {}
+            _ => predictor.reset(),
+        }
+    }
+    emit_suggestions(app);
+}
+
+pub fn emit_suggestions(app: &AppHandle) {
+    let (prefix, suggestions) = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        (state.predictor.current_word().to_string(), state.predictor.suggestions())
+    };
+    let _ = app.emit("osk:suggestions", serde_json::json!({
+        "prefix": prefix,
+        "suggestions": suggestions,
+    }));
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+pub fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn user_dictionary_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(USER_DICTIONARY_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text: &str) -> Predictor {
+        let mut predictor = Predictor::default();
+        predictor.push_text(text);
+        predictor
+    }
+
+    #[test]
+    fn suggests_most_frequent_completions() {
+        assert_eq!(typed("th").suggestions(), ["the", "that", "this"]);
+    }
+
+    #[test]
+    fn keeps_the_typed_capital() {
+        assert_eq!(typed("Th").suggestions(), ["The", "That", "This"]);
+    }
+
+    #[test]
+    fn word_ends_on_non_word_characters() {
+        let mut predictor = typed("hello wor");
+        assert_eq!(predictor.current_word(), "wor");
+        predictor.backspace();
+        assert_eq!(predictor.current_word(), "wo");
+        predictor.push_text(".");
+        assert_eq!(predictor.current_word(), "");
+    }
+
+    #[test]
+    fn learned_words_outrank_bundled_ones() {
+        let mut predictor = Predictor::default();
+        predictor.learn("thermostat");
+        predictor.push_text("th");
+        assert_eq!(predictor.suggestions()[0], "thermostat");
+    }
+
+    #[test]
+    fn incognito_does_not_learn() {
+        let mut predictor = Predictor {
+            incognito: true,
+            ..Predictor::default()
+        };
+        predictor.push_text("thermostat ");
+        predictor.push_text("th");
+        assert!(!predictor.suggestions().contains(&"thermostat".to_string()));
+    }
+
+    #[test]
+    fn replacement_completes_or_retypes() {
+        let predictor = typed("th");
+        assert_eq!(predictor.replacement_for("there"), (0, "ere".to_string()));
+        assert_eq!(predictor.replacement_for("was"), (2, "was".to_string()));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::actions::{default_media_buttons, default_osk_buttons, default_system_buttons, Action};
+use crate::hotspots::HotspotSlots;
+use crate::input_mapper::{default_radial_menus, OskMode, RadialSlot};
+use crate::layout::DEFAULT_LAYOUT;
+use crate::monitor::{CursorEdge, SpeedScaling};
+use crate::tray::TrayAction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+const PROFILES_FILE: &str = "profiles.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectionStrategy {
+    // Scan codes through the target's keyboard layout, unicode for anything the layout can't produce
+    #[default]
+    Auto,
+    Unicode,
+    Scancode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteShortcut {
+    #[default]
+    CtrlV,
+    // Terminals usually want Ctrl+Shift+V
+    CtrlShiftV,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub injection: InjectionStrategy,
+    pub paste_shortcut: PasteShortcut,
+    // Text this long (in characters) or longer goes through the clipboard
+    pub paste_threshold: usize,
+    pub char_delay_ms: u64,
+    // Button name (see `input_mapper::button_name`) -> action, for system mode
+    pub system_buttons: BTreeMap<String, Action>,
+    // Same, for the grid OSK. Start always closes it; South, East, Select and the D-pad
+    // keep their built-in navigation unless bound here.
+    pub osk_buttons: BTreeMap<String, Action>,
+    // Same, while the media layer is on
+    pub media_buttons: BTreeMap<String, Action>,
+    pub osk_mode: OskMode,
+    // Last OSK layout picked with this profile, see `layout::LayoutRegistry`
+    pub layout: String,
+    // Collect OSK typing in a buffer and send it on commit, see `staging`
+    pub staging: bool,
+    // Held while moving the right stick in the OSK: extend the selection, jump by words
+    pub caret_select_button: String,
+    pub caret_word_button: String,
+    // Multi-tap: dictionary disambiguation instead of cycling letters, and the cycle timeout
+    pub t9_predictive: bool,
+    pub t9_timeout_ms: u64,
+    // Unpinned clips kept in the clipboard history
+    pub clipboard_history_size: usize,
+    // Keep the history across restarts, as plain JSON in the config directory. Turn it off
+    // to keep clips in memory only.
+    pub clipboard_persist: bool,
+    // Copies made by one of these processes are not recorded
+    pub clipboard_exclude: Vec<String>,
+    // Slot sets for `Action::Radial`, by menu name, up to eight slots each
+    pub radial_menus: BTreeMap<String, Vec<RadialSlot>>,
+    // Extra entries in the tray menu
+    pub tray_actions: Vec<TrayAction>,
+    // Saved cursor positions, by monitor arrangement (see `monitor::layout_key`)
+    pub hotspots: BTreeMap<String, HotspotSlots>,
+    // Stick cursor speed per monitor, and what the desktop edges do
+    pub cursor_speed_scaling: SpeedScaling,
+    pub cursor_edge: CursorEdge,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            injection: InjectionStrategy::default(),
+            paste_shortcut: PasteShortcut::default(),
+            paste_threshold: 32,
+            char_delay_ms: 0,
+            system_buttons: default_system_buttons(),
+            osk_buttons: default_osk_buttons(),
+            media_buttons: default_media_buttons(),
+            osk_mode: OskMode::default(),
+            layout: DEFAULT_LAYOUT.to_string(),
+            staging: false,
+            caret_select_button: "ls".to_string(),
+            caret_word_button: "rs".to_string(),
+            t9_predictive: false,
+            t9_timeout_ms: 800,
+            clipboard_history_size: 50,
+            clipboard_persist: true,
+            clipboard_exclude: ["keepass", "1password", "bitwarden", "lastpass", "dashlane", "enpass"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            radial_menus: default_radial_menus(),
+            tray_actions: Vec::new(),
+            hotspots: BTreeMap::new(),
+            cursor_speed_scaling: SpeedScaling::default(),
+            cursor_edge: CursorEdge::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ProfileStore {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for ProfileStore {
+    fn default() -> Self {
+        let profile = Profile::default();
+        Self {
+            active: profile.name.clone(),
+            profiles: vec![profile],
+        }
+    }
+}
+
+impl ProfileStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let mut store = match store_path(app).map(fs::read_to_string) {
+            Some(Ok(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("Failed to parse {}: {}", PROFILES_FILE, e);
+                Self::default()
+            }),
+            _ => Self::default(),
+        };
+        if store.profiles.is_empty() {
+            store.profiles.push(Profile::default());
+        }
+        store
+    }
+
+    pub fn save(&self, app: &AppHandle) {
+        let Some(path) = store_path(app) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(e) = fs::write(&path, json) {
+                    eprintln!("Failed to save {}: {}", PROFILES_FILE, e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize profiles: {}", e),
+        }
+    }
+
+    pub fn active(&self) -> &Profile {
+        &self.profiles[self.active_index()]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Profile {
+        let index = self.active_index();
+        &mut self.profiles[index]
+    }
+
+    pub fn select(&mut self, name: &str) -> bool {
+        if self.profiles.iter().any(|p| p.name == name) {
+            self.active = name.to_string();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn upsert(&mut self, profile: Profile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    // Falls back to the first profile if the active name is stale
+    fn active_index(&self) -> usize {
+        self.profiles.iter().position(|p| p.name == self.active).unwrap_or(0)
+    }
+}
+
+fn store_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(PROFILES_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_round_trips_through_json() {
+        let mut store = ProfileStore::default();
+        store.upsert(Profile {
+            name: "couch".to_string(),
+            injection: InjectionStrategy::Unicode,
+            paste_shortcut: PasteShortcut::CtrlShiftV,
+            paste_threshold: 8,
+            ..Profile::default()
+        });
+        assert!(store.select("couch"));
+
+        let json = serde_json::to_string(&store).unwrap();
+        let loaded: ProfileStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        assert_eq!(loaded.active().name, "couch");
+        assert_eq!(loaded.active().injection, InjectionStrategy::Unicode);
+        assert_eq!(loaded.active().paste_shortcut, PasteShortcut::CtrlShiftV);
+        assert_eq!(loaded.active().paste_threshold, 8);
+    }
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let store: ProfileStore =
+            serde_json::from_str(r#"{ "active": "old", "profiles": [{ "name": "old", "injection": "scancode" }] }"#)
+                .unwrap();
+        let profile = store.active();
+        assert_eq!(profile.injection, InjectionStrategy::Scancode);
+        assert_eq!(profile.paste_threshold, Profile::default().paste_threshold);
+        assert_eq!(profile.system_buttons, default_system_buttons());
+    }
+
+    #[test]
+    fn stale_active_name_falls_back_to_first_profile() {
+        let mut store = ProfileStore {
+            active: "gone".to_string(),
+            ..ProfileStore::default()
+        };
+        assert_eq!(store.active().name, "default");
+        assert!(!store.select("gone"));
+    }
+
+    #[test]
+    fn upsert_replaces_by_name() {
+        let mut store = ProfileStore::default();
+        store.upsert(Profile {
+            paste_threshold: 1,
+            ..Profile::default()
+        });
+        assert_eq!(store.profiles.len(), 1);
+        assert_eq!(store.active().paste_threshold, 1);
+    }
+}
@@ -1,30 +1,40 @@
-use crate::{funcs, gamepad, tray};
+use crate::app_state::SharedAppState;
+use crate::profile::ProfileStore;
+use crate::{clipboard_history, funcs, gamepad, overlay, tray};
 use tauri::{App, Manager};
 
 pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    // Load profiles, layouts and the learned words before anything starts reading them
+    let profiles = ProfileStore::load(app.handle());
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        state.profiles = profiles;
+        state.predictor.load_user_dictionary(app.handle());
+        state.layouts.load(app.handle());
+        state.ime.load(app.handle());
+        let persist = state.profiles.active().clipboard_persist;
+        state.clipboard.load(app.handle(), persist);
+    }
+
     // Initialize tray
     tray::create_tray(app.handle())?;
 
     // Start gamepad listener
     gamepad::init_gamepad_listener(app.handle().clone());
 
+    // Start clipboard monitor
+    clipboard_history::start_monitor(app.handle().clone());
+
+    overlay::init(app.handle());
+
     // Configure main window
     if let Some(window) = app.get_webview_window("main") {
         // Set always on top
         let _ = window.set_always_on_top(true);
 
         // Position at bottom right
-        if let Ok(Some(monitor)) = window.current_monitor() {
-            let screen_size = monitor.size();
-            let window_size = window.outer_size().unwrap_or(tauri::PhysicalSize { width: 800, height: 300 });
-            
-            const MARGIN: i32 = 25;
-
-            let x = screen_size.width as i32 - window_size.width as i32 - MARGIN;
-            let y = screen_size.height as i32 - window_size.height as i32 - MARGIN;
-            
-            let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
-        }
+        funcs::place_osk_window(&window);
 
         #[cfg(target_os = "windows")]
         {
@@ -0,0 +1,265 @@
+use crate::app_state::SharedAppState;
+use crate::text_delivery::{self, TextMethod};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+// Text collected on the OSK before it goes to the target in one go
+#[derive(Default)]
+pub struct StagingBuffer {
+    text: Vec<char>,
+    // Caret position, in characters
+    cursor: usize,
+    // Enter committed the buffer; its repeats and release must not reach the target
+    enter_held: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StagingAction {
+    Commit,
+    Clear,
+    DeleteWord,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+}
+
+impl StagingBuffer {
+    fn insert(&mut self, text: &str) {
+        for c in text.chars() {
+            self.text.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.cursor);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    // Start of the word before the caret, skipping the whitespace in between
+    fn word_start(&self) -> usize {
+        let mut i = self.cursor;
+        while i > 0 && self.text[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !self.text[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    fn word_end(&self) -> usize {
+        let mut i = self.cursor;
+        while i < self.text.len() && self.text[i].is_whitespace() {
+            i += 1;
+        }
+        while i < self.text.len() && !self.text[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    // Applies an action, returning the text to send for a commit
+    fn apply(&mut self, action: StagingAction) -> Option<String> {
+        match action {
+            StagingAction::Commit => {
+                let text: String = self.text.drain(..).collect();
+                self.cursor = 0;
+                return Some(text);
+            }
+            StagingAction::Clear => {
+                self.text.clear();
+                self.cursor = 0;
+            }
+            StagingAction::DeleteWord => {
+                let start = self.word_start();
+                self.text.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            StagingAction::Left => self.cursor = self.cursor.saturating_sub(1),
+            StagingAction::Right => self.cursor = (self.cursor + 1).min(self.text.len()),
+            StagingAction::WordLeft => self.cursor = self.word_start(),
+            StagingAction::WordRight => self.cursor = self.word_end(),
+            StagingAction::Home => self.cursor = 0,
+            StagingAction::End => self.cursor = self.text.len(),
+        }
+        None
+    }
+}
+
+pub fn enabled(app: &AppHandle) -> bool {
+    app.state::<SharedAppState>().lock().unwrap().profiles.active().staging
+}
+
+// Typed text goes into the buffer instead of the target. False when staging is off.
+pub fn capture_text(app: &AppHandle, text: &str) -> bool {
+    if !enabled(app) {
+        return false;
+    }
+    app.state::<SharedAppState>().lock().unwrap().staging.insert(text);
+    emit_state(app);
+    true
+}
+
+// Editing keys act on the buffer. Enter commits it; with nothing staged it goes
+// through as usual, like any key the buffer has no use for. Releases of captured keys
+// are swallowed too.
+pub fn capture_key(app: &AppHandle, phase: &str, key: &str) -> bool {
+    if !enabled(app) {
+        return false;
+    }
+    let state_handle = app.state::<SharedAppState>();
+    let mut state = state_handle.lock().unwrap();
+    let buffer = &mut state.staging;
+    if key == "{enter}" && phase != "down" && buffer.enter_held {
+        buffer.enter_held = phase != "up";
+        return true;
+    }
+    if phase == "up" {
+        return is_editing_key(key);
+    }
+
+    match key {
+        "{bksp}" => buffer.backspace(),
+        "{del}" => buffer.delete(),
+        "{space}" => buffer.insert(" "),
+        "{tab}" => buffer.insert("\t"),
+        "{arrowleft}" => {
+            buffer.apply(StagingAction::Left);
+        }
+        "{arrowright}" => {
+            buffer.apply(StagingAction::Right);
+        }
+        "{home}" => {
+            buffer.apply(StagingAction::Home);
+        }
+        "{end}" => {
+            buffer.apply(StagingAction::End);
+        }
+        "{enter}" if !buffer.text.is_empty() => {
+            buffer.enter_held = true;
+            drop(state);
+            run(app, StagingAction::Commit);
+            return true;
+        }
+        _ => return false,
+    }
+    drop(state);
+    emit_state(app);
+    true
+}
+
+fn is_editing_key(key: &str) -> bool {
+    matches!(
+        key,
+        "{bksp}" | "{del}" | "{space}" | "{tab}" | "{arrowleft}" | "{arrowright}" | "{home}" | "{end}"
+    )
+}
+
+pub fn run(app: &AppHandle, action: StagingAction) {
+    let text = app.state::<SharedAppState>().lock().unwrap().staging.apply(action);
+    emit_state(app);
+
+    if let Some(text) = text.filter(|t| !t.is_empty()) {
+        text_delivery::deliver_in_background(app, text, TextMethod::Auto);
+    }
+}
+
+// Whatever is still staged goes to the target when the OSK closes
+pub fn commit_on_close(app: &AppHandle) {
+    if enabled(app) {
+        run(app, StagingAction::Commit);
+    }
+}
+
+pub fn emit_state(app: &AppHandle) {
+    let payload = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        serde_json::json!({
+            "enabled": state.profiles.active().staging,
+            "text": state.staging.text.iter().collect::<String>(),
+            "cursor": state.staging.cursor,
+        })
+    };
+    let _ = app.emit("osk:staging", payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(text: &str) -> StagingBuffer {
+        let mut buffer = StagingBuffer::default();
+        buffer.insert(text);
+        buffer
+    }
+
+    fn text(buffer: &StagingBuffer) -> String {
+        buffer.text.iter().collect()
+    }
+
+    #[test]
+    fn typing_inserts_at_the_caret() {
+        let mut b = buffer("helo");
+        b.apply(StagingAction::Left);
+        b.insert("l");
+        assert_eq!(text(&b), "hello");
+        assert_eq!(b.cursor, 4);
+    }
+
+    #[test]
+    fn backspace_and_delete_stop_at_the_ends() {
+        let mut b = buffer("ab");
+        b.delete();
+        assert_eq!(text(&b), "ab");
+        b.backspace();
+        b.backspace();
+        b.backspace();
+        assert_eq!(text(&b), "");
+    }
+
+    #[test]
+    fn word_jumps_skip_the_whitespace_between() {
+        let mut b = buffer("one  two three");
+        b.apply(StagingAction::WordLeft);
+        assert_eq!(b.cursor, 9);
+        b.apply(StagingAction::WordLeft);
+        assert_eq!(b.cursor, 5);
+        b.apply(StagingAction::Home);
+        b.apply(StagingAction::WordRight);
+        assert_eq!(b.cursor, 3);
+        b.apply(StagingAction::WordRight);
+        assert_eq!(b.cursor, 8);
+    }
+
+    #[test]
+    fn delete_word_removes_back_to_the_word_start() {
+        let mut b = buffer("hello big world");
+        b.apply(StagingAction::WordLeft);
+        b.apply(StagingAction::DeleteWord);
+        assert_eq!(text(&b), "hello world");
+        assert_eq!(b.cursor, 6);
+    }
+
+    #[test]
+    fn commit_empties_the_buffer() {
+        let mut b = buffer("done");
+        assert_eq!(b.apply(StagingAction::Commit).as_deref(), Some("done"));
+        assert_eq!(text(&b), "");
+        assert_eq!(b.cursor, 0);
+        assert_eq!(b.apply(StagingAction::Clear), None);
+    }
+}
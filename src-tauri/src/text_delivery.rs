@@ -0,0 +1,490 @@
+use crate::alternates::{self, Press};
+use crate::app_state::SharedAppState;
+use crate::clipboard_history;
+use crate::compose::{self, Composed};
+use crate::ime;
+use crate::native_window;
+use crate::prediction;
+use crate::profile::PasteShortcut;
+use crate::staging;
+use enigo::{Direction, Enigo, Key, Keyboard};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// Time for the clipboard owner change to land before we paste, and for the
+// target to read the clipboard before we put the old content back.
+const CLIPBOARD_SETTLE: Duration = Duration::from_millis(20);
+const CLIPBOARD_RESTORE_DELAY: Duration = Duration::from_millis(150);
+
+// The physical V key, so Ctrl+V still pastes on layouts without a Latin 'v'.
+// Scan code on Windows, X11 keycode on Linux, virtual key code on macOS.
+#[cfg(target_os = "windows")]
+const V_KEY: u16 = 0x2F;
+#[cfg(target_os = "macos")]
+const V_KEY: u16 = 0x09;
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const V_KEY: u16 = 55;
+
+// Work handed off by the gamepad thread, done in order on one delivery thread
+enum Job {
+    Text(String, TextMethod),
+    Key(Key),
+}
+
+static DELIVERY: OnceLock<Sender<Job>> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TextMethod {
+    // Paste anything at or above the profile's paste threshold, type the rest
+    #[default]
+    Auto,
+    Paste,
+    Type,
+}
+
+pub fn deliver(app: &AppHandle, enigo: &mut Enigo, text: &str, method: TextMethod) {
+    if text.is_empty() {
+        return;
+    }
+
+    let (paste_threshold, paste_shortcut, char_delay_ms) = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        let profile = state.profiles.active();
+        (profile.paste_threshold, profile.paste_shortcut, profile.char_delay_ms)
+    };
+
+    let target = focus_target(app);
+
+    let paste = match method {
+        TextMethod::Auto => text.chars().count() >= paste_threshold,
+        TextMethod::Paste => true,
+        TextMethod::Type => false,
+    };
+
+    if !paste || !paste_text(app, enigo, text, paste_shortcut) {
+        type_text(app, enigo, text, target, Duration::from_millis(char_delay_ms));
+    }
+}
+
+// Pastes and per-character delays sleep, so callers on the gamepad thread hand the text off
+pub fn deliver_in_background(app: &AppHandle, text: String, method: TextMethod) {
+    queue(app, Job::Text(text, method));
+}
+
+// A word and the space typed after it must not overtake each other, so everything goes
+// through the same thread
+fn queue(app: &AppHandle, job: Job) {
+    let sender = DELIVERY.get_or_init(|| {
+        let (sender, jobs) = mpsc::channel();
+        let app = app.clone();
+        thread::spawn(move || {
+            let mut enigo = match Enigo::new(&enigo::Settings::default()) {
+                Ok(e) => e,
+                Err(e) => {
+                    eprintln!("Failed to init enigo: {:?}", e);
+                    return;
+                }
+            };
+            for job in jobs {
+                match job {
+                    Job::Text(text, method) => deliver(&app, &mut enigo, &text, method),
+                    Job::Key(key) => {
+                        focus_target(&app);
+                        let _ = enigo.key(key, Direction::Click);
+                    }
+                }
+            }
+        });
+        sender
+    });
+    if sender.send(job).is_err() {
+        eprintln!("Text delivery thread is not running");
+    }
+}
+
+// Text typed by the backend OSK modes, with the same bookkeeping `send_key` does
+pub fn osk_text(app: &AppHandle, text: &str) {
+    prediction::observe_key(app, None, Some(text));
+    if staging::capture_text(app, text) {
+        return;
+    }
+    deliver_in_background(app, text.to_string(), TextMethod::Type);
+}
+
+// Special keys use the simple-keyboard names, e.g. "{bksp}"
+pub fn osk_key(app: &AppHandle, name: &str) {
+    let key = match name {
+        "{enter}" => Key::Return,
+        "{bksp}" => Key::Backspace,
+        "{space}" => Key::Space,
+        "{tab}" => Key::Tab,
+        "{esc}" => Key::Escape,
+        "{arrowup}" => Key::UpArrow,
+        "{arrowdown}" => Key::DownArrow,
+        "{arrowleft}" => Key::LeftArrow,
+        "{arrowright}" => Key::RightArrow,
+        "{home}" => Key::Home,
+        "{end}" => Key::End,
+        "{pgup}" => Key::PageUp,
+        "{pgdn}" => Key::PageDown,
+        "{del}" => Key::Delete,
+        "{f1}" => Key::F1,
+        "{f2}" => Key::F2,
+        "{f3}" => Key::F3,
+        "{f4}" => Key::F4,
+        "{f5}" => Key::F5,
+        "{f6}" => Key::F6,
+        "{f7}" => Key::F7,
+        "{f8}" => Key::F8,
+        "{f9}" => Key::F9,
+        "{f10}" => Key::F10,
+        "{f11}" => Key::F11,
+        "{f12}" => Key::F12,
+        "{playpause}" => Key::MediaPlayPause,
+        #[cfg(not(target_os = "macos"))]
+        "{mediastop}" => Key::MediaStop,
+        "{nexttrack}" => Key::MediaNextTrack,
+        "{prevtrack}" => Key::MediaPrevTrack,
+        "{mute}" => Key::VolumeMute,
+        "{volumedown}" => Key::VolumeDown,
+        "{volumeup}" => Key::VolumeUp,
+        _ => return,
+    };
+    prediction::observe_key(app, Some(name), None);
+    if staging::capture_key(app, "down", name) {
+        return;
+    }
+    queue(app, Job::Key(key));
+}
+
+#[derive(serde::Deserialize)]
+pub struct KeyPayload {
+    phase: String,
+    key: Option<String>,
+    // Raw scan code and held modifiers, for the Windows injector
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    scan_code: Option<u16>,
+    text: Option<String>,
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    modifiers: Option<Vec<String>>,
+}
+
+// Everything a key from the OSK goes through: composition, accents, prediction and
+// staging get the first look, whatever is left is injected
+pub fn send_key(app_handle: AppHandle, mut payload: KeyPayload) {
+    match ime::intercept(&app_handle, &payload.phase, payload.key.as_deref(), payload.text.as_deref()) {
+        Composed::Pass => {}
+        Composed::Pending => return,
+        Composed::Done(text) => {
+            commit_unicode(app_handle, text);
+            return;
+        }
+    }
+
+    match alternates::intercept(&app_handle, &payload.phase, payload.text.as_deref()) {
+        Press::Pass => {}
+        Press::Hold => return,
+        Press::Type(text) => {
+            payload.phase = "down".to_string();
+            payload.text = Some(text);
+        }
+        Press::Commit(text) => {
+            commit_unicode(app_handle, text);
+            return;
+        }
+    }
+
+    match compose::intercept(&app_handle, &payload.phase, payload.key.as_deref(), payload.text.as_deref()) {
+        Composed::Pass => {}
+        Composed::Pending => return,
+        Composed::Done(text) => {
+            commit_unicode(app_handle, text);
+            return;
+        }
+    }
+
+    if payload.phase != "up" {
+        prediction::observe_key(&app_handle, payload.key.as_deref(), payload.text.as_deref());
+    }
+
+    let staged = match (payload.key.as_deref(), payload.text.as_deref()) {
+        (Some(key), _) => staging::capture_key(&app_handle, &payload.phase, key),
+        (None, Some(text)) => payload.phase != "up" && staging::capture_text(&app_handle, text),
+        _ => false,
+    };
+    if !staged {
+        inject_key(&app_handle, payload);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn inject_key(app_handle: &AppHandle, payload: KeyPayload) {
+    use crate::injection;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{INPUT, SendInput};
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, SetForegroundWindow};
+    use windows::Win32::Foundation::HWND;
+    use raw_window_handle::HasWindowHandle;
+    use std::mem::size_of;
+
+    // Safety: Check if we are stealing focus and restore target
+    let current_fg = unsafe { GetForegroundWindow() };
+    let state = app_handle.state::<SharedAppState>();
+    let (mut target, strategy) = {
+        let state = state.lock().unwrap();
+        (state.target_hwnd, state.profiles.active().injection)
+    };
+    
+    if let Some(window) = app_handle.get_webview_window("main") {
+         if let Ok(handle) = window.window_handle() {
+             let raw = handle.as_raw();
+             if let raw_window_handle::RawWindowHandle::Win32(win32_handle) = raw {
+                 let my_hwnd = win32_handle.hwnd.get();
+                 if current_fg.0 as isize == my_hwnd {
+                     // We are focused! Switch back to target.
+                     if target != 0 {
+                         unsafe { let _ = SetForegroundWindow(HWND(target as _)); }
+                         // Small delay to allow focus switch
+                         std::thread::sleep(std::time::Duration::from_millis(10));
+                     }
+                 } else if target != 0 && (current_fg.0 as isize) != target {
+                     // Focus changed to another window. Update target?
+                     // Or just send to whatever is foreground (default behavior of SendInput).
+                     // If we want to stick to target, we should switch.
+                     // But usually user wants to type where they clicked.
+                     // So we update our target to current foreground.
+                     target = current_fg.0 as isize;
+                     state.lock().unwrap().target_hwnd = target;
+                 }
+             }
+         }
+    }
+
+    let mut inputs = Vec::new();
+    let up = payload.phase == "up";
+
+    // Handle modifiers: press before the key on "down", release after it on "up"
+    let modifier_codes: Vec<u16> = payload
+        .modifiers
+        .iter()
+        .flatten()
+        .filter_map(|m| injection::modifier_scan_code(m))
+        .collect();
+
+    if payload.phase == "down" {
+        for sc in &modifier_codes {
+            inputs.push(injection::scan_input(*sc, false));
+        }
+    }
+
+    if let Some(sc) = payload.scan_code {
+        inputs.push(injection::scan_input(sc, up));
+    } else if let Some(key_str) = payload.key {
+         let sc = match key_str.as_str() {
+             "{enter}" => 0x1C,
+             "{bksp}" => 0x0E,
+             "{space}" => 0x39,
+             "{tab}" => 0x0F,
+             "{esc}" => 0x01,
+             "{shift}" => 0x2A,
+             "{lock}" => 0x3A,
+             "{arrowup}" => 0xE048,
+             "{arrowdown}" => 0xE050,
+             "{arrowleft}" => 0xE04B,
+             "{arrowright}" => 0xE04D,
+             "{home}" => 0xE047,
+             "{end}" => 0xE04F,
+             "{pgup}" => 0xE049,
+             "{pgdn}" => 0xE051,
+             "{ins}" => 0xE052,
+             "{del}" => 0xE053,
+             "{f1}" => 0x3B,
+             "{f2}" => 0x3C,
+             "{f3}" => 0x3D,
+             "{f4}" => 0x3E,
+             "{f5}" => 0x3F,
+             "{f6}" => 0x40,
+             "{f7}" => 0x41,
+             "{f8}" => 0x42,
+             "{f9}" => 0x43,
+             "{f10}" => 0x44,
+             "{f11}" => 0x57,
+             "{f12}" => 0x58,
+             "{playpause}" => 0xE022,
+             "{mediastop}" => 0xE024,
+             "{nexttrack}" => 0xE019,
+             "{prevtrack}" => 0xE010,
+             "{mute}" => 0xE020,
+             "{volumedown}" => 0xE02E,
+             "{volumeup}" => 0xE030,
+             _ => 0
+         };
+         
+         if sc != 0 {
+            inputs.push(injection::scan_input(sc, up));
+         }
+    } else if let Some(text) = payload.text {
+         if payload.phase == "down" || payload.phase == "repeat" {
+             inputs.extend(injection::text_inputs(&text, strategy, target));
+         }
+    }
+
+    if up {
+        for sc in modifier_codes.iter().rev() {
+            inputs.push(injection::scan_input(*sc, true));
+        }
+    }
+
+    if !inputs.is_empty() {
+        unsafe {
+            SendInput(&inputs, size_of::<INPUT>() as i32);
+        }
+    }
+}
+
+// Keys from the OSK frontend are only injected on Windows so far
+#[cfg(not(target_os = "windows"))]
+fn inject_key(_app_handle: &AppHandle, _payload: KeyPayload) {}
+
+// Same path as a key pressed on the OSK, for gamepad bindings
+pub fn press_osk_key(app_handle: &AppHandle, key: &str, phase: &str) {
+    let payload = KeyPayload {
+        phase: phase.to_string(),
+        key: Some(key.to_string()),
+        scan_code: None,
+        text: None,
+        modifiers: None,
+    };
+    send_key(app_handle.clone(), payload);
+}
+
+// Accents and composed characters, which the target's layout may not be able to type
+fn commit_unicode(app_handle: AppHandle, text: String) {
+    prediction::observe_key(&app_handle, None, Some(&text));
+    if staging::capture_text(&app_handle, &text) {
+        return;
+    }
+    std::thread::spawn(move || {
+        let mut enigo = match Enigo::new(&enigo::Settings::default()) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Failed to init enigo: {:?}", e);
+                return;
+            }
+        };
+        unicode_text(&app_handle, &mut enigo, &text);
+    });
+}
+
+// Make sure the OSK isn't the foreground window before injecting anything
+pub fn focus_target(app: &AppHandle) -> isize {
+    let target = app.state::<SharedAppState>().lock().unwrap().target_hwnd;
+    if native_window::foreground() == native_window::own_handle(app) && native_window::activate(target) {
+        thread::sleep(Duration::from_millis(10));
+    }
+    target
+}
+
+// Returns false if the clipboard couldn't be used, so the caller can type instead.
+// Only text can be put back afterwards, so an image or files on the clipboard (or an
+// empty one) means typing rather than losing them.
+fn paste_text(app: &AppHandle, enigo: &mut Enigo, text: &str, shortcut: PasteShortcut) -> bool {
+    let clipboard = app.clipboard();
+    let Ok(previous) = clipboard.read_text() else {
+        return false;
+    };
+
+    // Keep our own write and restore out of the clipboard history
+    clipboard_history::set_paused(app, true);
+    if let Err(e) = clipboard.write_text(text) {
+        eprintln!("Failed to write clipboard: {}", e);
+        clipboard_history::set_paused(app, false);
+        return false;
+    }
+    thread::sleep(CLIPBOARD_SETTLE);
+
+    let with_shift = shortcut == PasteShortcut::CtrlShiftV;
+    let _ = enigo.key(Key::Control, Direction::Press);
+    if with_shift {
+        let _ = enigo.key(Key::Shift, Direction::Press);
+    }
+    let _ = enigo.raw(V_KEY, Direction::Click);
+    if with_shift {
+        let _ = enigo.key(Key::Shift, Direction::Release);
+    }
+    let _ = enigo.key(Key::Control, Direction::Release);
+
+    thread::sleep(CLIPBOARD_RESTORE_DELAY);
+    let _ = clipboard.write_text(previous);
+    clipboard_history::set_paused(app, false);
+
+    true
+}
+
+// Skips the layout lookup, for characters the target layout may not have
+#[cfg(target_os = "windows")]
+pub fn unicode_text(app: &AppHandle, _enigo: &mut Enigo, text: &str) {
+    use crate::injection;
+    use crate::profile::InjectionStrategy;
+    use std::mem::size_of;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{SendInput, INPUT};
+
+    let target = focus_target(app);
+    let inputs = injection::text_inputs(text, InjectionStrategy::Unicode, target);
+    unsafe {
+        SendInput(&inputs, size_of::<INPUT>() as i32);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn unicode_text(app: &AppHandle, enigo: &mut Enigo, text: &str) {
+    focus_target(app);
+    let _ = enigo.text(text);
+}
+
+#[cfg(target_os = "windows")]
+fn type_text(app: &AppHandle, _enigo: &mut Enigo, text: &str, target: isize, delay: Duration) {
+    use crate::injection;
+    use std::mem::size_of;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{SendInput, INPUT};
+
+    let strategy = app.state::<SharedAppState>().lock().unwrap().profiles.active().injection;
+
+    // Without a delay the whole string goes out in a single SendInput batch
+    if delay.is_zero() {
+        let inputs = injection::text_inputs(text, strategy, target);
+        unsafe {
+            SendInput(&inputs, size_of::<INPUT>() as i32);
+        }
+        return;
+    }
+
+    let mut buf = [0u8; 4];
+    for c in text.chars() {
+        let inputs = injection::text_inputs(c.encode_utf8(&mut buf), strategy, target);
+        unsafe {
+            SendInput(&inputs, size_of::<INPUT>() as i32);
+        }
+        thread::sleep(delay);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn type_text(_app: &AppHandle, enigo: &mut Enigo, text: &str, _target: isize, delay: Duration) {
+    if delay.is_zero() {
+        let _ = enigo.text(text);
+        return;
+    }
+
+    let mut buf = [0u8; 4];
+    for c in text.chars() {
+        let _ = enigo.text(c.encode_utf8(&mut buf));
+        thread::sleep(delay);
+    }
+}
@@ -1,23 +1,84 @@
+use crate::actions::{self, Action};
 use crate::app_state::SharedAppState;
 use crate::funcs;
+use enigo::Direction;
+use serde::{Deserialize, Serialize};
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter, Manager, Runtime,
 };
 
-pub fn create_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()>
-where
-    AppHandle<R>: Manager<tauri::Wry>,
-{
+const ACTION_PREFIX: &str = "action:";
+
+// Extra tray menu entry from the active profile, e.g. a launcher
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TrayAction {
+    pub label: String,
+    pub action: Action,
+}
+
+fn build_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
     let show_osk_i = MenuItem::with_id(app, "show_osk", "Show OSK", true, None::<&str>)?;
     let hide_osk_i = MenuItem::with_id(app, "hide_osk", "Hide OSK", true, None::<&str>)?;
     let toggle_active_i =
         MenuItem::with_id(app, "toggle_active", "Toggle Active", true, None::<&str>)?;
     let exit_i = MenuItem::with_id(app, "exit", "Exit", true, None::<&str>)?;
 
-    let menu = Menu::with_items(app, &[&show_osk_i, &hide_osk_i, &toggle_active_i, &exit_i])?;
+    let menu = Menu::with_items(app, &[&show_osk_i, &hide_osk_i, &toggle_active_i])?;
+
+    let tray_actions = app.state::<SharedAppState>().lock().unwrap().profiles.active().tray_actions.clone();
+    if !tray_actions.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+        for (i, tray_action) in tray_actions.iter().enumerate() {
+            let id = format!("{}{}", ACTION_PREFIX, i);
+            menu.append(&MenuItem::with_id(app, id, &tray_action.label, true, None::<&str>)?)?;
+        }
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+
+    menu.append(&exit_i)?;
+    Ok(menu)
+}
+
+// Rebuilds the menu after the active profile changed
+pub fn refresh(app: &AppHandle) {
+    let (Some(tray), Ok(menu)) = (app.tray_by_id("main-tray"), build_menu(app)) else {
+        return;
+    };
+    let _ = tray.set_menu(Some(menu));
+}
+
+fn run_tray_action(app: &AppHandle, index: usize) {
+    let action = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.profiles.active().tray_actions.get(index).map(|a| a.action.clone())
+    };
+    let Some(action) = action else {
+        return;
+    };
+    // Menu events come in on the main thread, which must not block on key injection
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut enigo = match enigo::Enigo::new(&enigo::Settings::default()) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Failed to init enigo: {:?}", e);
+                return;
+            }
+        };
+        actions::run(&action, Direction::Press, &app, &mut enigo);
+        actions::run(&action, Direction::Release, &app, &mut enigo);
+    });
+}
+
+pub fn create_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()>
+where
+    AppHandle<R>: Manager<tauri::Wry>,
+{
+    let menu = build_menu(app)?;
 
     // Assuming icon.ico exists in icons folder
     let icon = Image::from_bytes(include_bytes!("../icons/icon.ico")).expect("Failed to load icon");
@@ -41,7 +102,11 @@ where
             "exit" => {
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(index) = id.strip_prefix(ACTION_PREFIX).and_then(|i| i.parse().ok()) {
+                    run_tray_action(app, index);
+                }
+            }
         })
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click { .. } = event {
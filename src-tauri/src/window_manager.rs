@@ -0,0 +1,193 @@
+use crate::app_state::SharedAppState;
+use crate::monitor::{self, MonitorInfo, Rect};
+use crate::native_window;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+// Close only goes through once its button has been held this long
+const CLOSE_HOLD: Duration = Duration::from_millis(800);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowOp {
+    Minimize,
+    // Maximize, or restore when already maximized
+    Maximize,
+    Close,
+    SnapLeft,
+    SnapRight,
+    NextMonitor,
+    // Sticks move and resize the window until the next button press
+    Grab,
+}
+
+#[derive(Default)]
+pub struct WindowManager {
+    close_pressed: Option<Instant>,
+    pub grab: Option<Grab>,
+}
+
+pub struct Grab {
+    pub handle: isize,
+    // (x, y, width, height), fractional pixels carried over between ticks
+    pub rect: (f32, f32, f32, f32),
+}
+
+// The typing target while the OSK is open, the foreground window otherwise
+pub fn target(app: &AppHandle) -> isize {
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        if state.osk_open {
+            return state.target_hwnd;
+        }
+    }
+    let fg = native_window::foreground();
+    if fg == native_window::own_handle(app) {
+        0
+    } else {
+        fg
+    }
+}
+
+pub fn run(app: &AppHandle, op: WindowOp, pressed: bool) {
+    if op == WindowOp::Close {
+        let held = {
+            let state_handle = app.state::<SharedAppState>();
+            let mut state = state_handle.lock().unwrap();
+            if pressed {
+                state.window_manager.close_pressed = Some(Instant::now());
+                return;
+            }
+            state.window_manager.close_pressed.take().is_some_and(|t| t.elapsed() >= CLOSE_HOLD)
+        };
+        if held {
+            native_window::close(target(app));
+        }
+        return;
+    }
+    if !pressed {
+        return;
+    }
+
+    let handle = target(app);
+    if handle == 0 {
+        return;
+    }
+    match op {
+        WindowOp::Minimize => {
+            native_window::minimize(handle);
+        }
+        WindowOp::Maximize => {
+            native_window::toggle_maximize(handle);
+        }
+        WindowOp::SnapLeft => snap(app, handle, false),
+        WindowOp::SnapRight => snap(app, handle, true),
+        WindowOp::NextMonitor => next_monitor(app, handle),
+        WindowOp::Grab => start_grab(app, handle),
+        WindowOp::Close => {}
+    }
+}
+
+// Monitor holding the window's centre, the first one if it is off screen
+fn monitor_of(monitors: &[MonitorInfo], rect: Rect) -> Option<&MonitorInfo> {
+    let (x, y) = monitor::centre(&rect);
+    monitors.get(monitor::index_at(monitors, x, y))
+}
+
+fn snap(app: &AppHandle, handle: isize, right: bool) {
+    let Some(rect) = native_window::rect(handle) else {
+        return;
+    };
+    let monitors = monitor::list(app);
+    let Some(monitor) = monitor_of(&monitors, rect) else {
+        return;
+    };
+    let (x, y, w, h) = snapped(monitor.work_area, right);
+    native_window::set_rect(handle, x, y, w, h);
+}
+
+// Left or right half of the area; the right half takes the odd pixel
+fn snapped((x, y, w, h): Rect, right: bool) -> Rect {
+    let half = w / 2;
+    if right {
+        (x + half, y, w - half, h)
+    } else {
+        (x, y, half, h)
+    }
+}
+
+// Same place and relative size on the next monitor, wrapping around
+fn next_monitor(app: &AppHandle, handle: isize) {
+    let Some(rect) = native_window::rect(handle) else {
+        return;
+    };
+    let monitors = monitor::list(app);
+    if monitors.len() < 2 {
+        return;
+    }
+    let (cx, cy) = monitor::centre(&rect);
+    let index = monitor::index_at(&monitors, cx, cy);
+    let (from, to) = (monitors[index].work_area, monitors[(index + 1) % monitors.len()].work_area);
+    let (x, y, w, h) = carried(rect, from, to);
+    native_window::set_rect(handle, x, y, w, h);
+}
+
+// `rect` moved from one area to another, keeping its offset and size relative to the area
+fn carried(rect: Rect, from: Rect, to: Rect) -> Rect {
+    let scale_x = to.2 as f32 / from.2 as f32;
+    let scale_y = to.3 as f32 / from.3 as f32;
+    (
+        to.0 + ((rect.0 - from.0) as f32 * scale_x) as i32,
+        to.1 + ((rect.1 - from.1) as f32 * scale_y) as i32,
+        (rect.2 as f32 * scale_x) as i32,
+        (rect.3 as f32 * scale_y) as i32,
+    )
+}
+
+fn start_grab(app: &AppHandle, handle: isize) {
+    let Some((x, y, w, h)) = native_window::rect(handle) else {
+        return;
+    };
+    app.state::<SharedAppState>().lock().unwrap().window_manager.grab = Some(Grab {
+        handle,
+        rect: (x as f32, y as f32, w as f32, h as f32),
+    });
+}
+
+pub fn is_grabbing(app: &AppHandle) -> bool {
+    app.state::<SharedAppState>().lock().unwrap().window_manager.grab.is_some()
+}
+
+pub fn end_grab(app: &AppHandle) {
+    app.state::<SharedAppState>().lock().unwrap().window_manager.grab = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapping_splits_the_area_in_half() {
+        let area = (0, 40, 1921, 1000);
+        assert_eq!(snapped(area, false), (0, 40, 960, 1000));
+        assert_eq!(snapped(area, true), (960, 40, 961, 1000));
+    }
+
+    #[test]
+    fn carried_window_keeps_its_relative_place_and_size() {
+        let from = (0, 0, 1920, 1080);
+        let to = (1920, 0, 3840, 2160);
+        assert_eq!(carried((480, 270, 960, 540), from, to), (2880, 540, 1920, 1080));
+        assert_eq!(carried((2880, 540, 1920, 1080), to, from), (480, 270, 960, 540));
+    }
+
+    #[test]
+    fn window_belongs_to_the_monitor_holding_its_centre() {
+        let monitors = monitor::tests::monitors();
+        assert_eq!(monitor_of(&monitors, (1800, 100, 400, 300)).unwrap().rect.0, 1920);
+        assert_eq!(monitor_of(&monitors, (1600, 100, 400, 300)).unwrap().rect.0, 0);
+        assert_eq!(monitor_of(&monitors, (-5000, 0, 100, 100)).unwrap().rect.0, 0);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::app_state::SharedAppState;
+use crate::funcs;
+use crate::native_window;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WindowInfo {
+    pub handle: isize,
+    pub title: String,
+    pub process: Option<String>,
+}
+
+pub struct Switcher {
+    windows: Vec<WindowInfo>,
+    selected: usize,
+    // The switcher borrowed the OSK window as its overlay and hides it again
+    shown_window: bool,
+    // Left stick direction currently held, see `input_mapper::stick_steps`
+    pub stick_x: i32,
+    pub stick_y: i32,
+}
+
+// Titled windows other than the OSK, front to back
+pub fn windows(app: &AppHandle) -> Vec<WindowInfo> {
+    let own = native_window::own_handle(app);
+    native_window::list()
+        .into_iter()
+        .filter(|&handle| handle != own)
+        .filter_map(|handle| {
+            let title = native_window::title(handle).filter(|t| !t.trim().is_empty())?;
+            Some(WindowInfo {
+                handle,
+                title,
+                process: native_window::process_name(handle),
+            })
+        })
+        .collect()
+}
+
+pub fn is_open(app: &AppHandle) -> bool {
+    app.state::<SharedAppState>().lock().unwrap().switcher.is_some()
+}
+
+// The window behind the current one, like Alt+Tab
+fn first_selection(windows: &[WindowInfo], foreground: isize) -> usize {
+    if windows.len() > 1 && windows[0].handle == foreground {
+        1
+    } else {
+        0
+    }
+}
+
+// Wraps around both ends of the list
+fn stepped(selected: usize, step: i32, len: usize) -> usize {
+    (selected as i32 + step).rem_euclid(len as i32) as usize
+}
+
+pub fn open(app: &AppHandle) {
+    if is_open(app) {
+        return;
+    }
+    let windows = windows(app);
+    let selected = first_selection(&windows, native_window::foreground());
+
+    let shown_window = funcs::show_overlay(app);
+    app.state::<SharedAppState>().lock().unwrap().switcher = Some(Switcher {
+        windows,
+        selected,
+        shown_window,
+        stick_x: 0,
+        stick_y: 0,
+    });
+    emit_state(app);
+}
+
+pub fn close(app: &AppHandle) -> Option<Switcher> {
+    let switcher = app.state::<SharedAppState>().lock().unwrap().switcher.take()?;
+    emit_state(app);
+    if switcher.shown_window {
+        funcs::hide_overlay(app);
+    }
+    Some(switcher)
+}
+
+pub fn step(app: &AppHandle, step: i32) {
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        let Some(switcher) = state.switcher.as_mut() else {
+            return;
+        };
+        if switcher.windows.is_empty() {
+            return;
+        }
+        switcher.selected = stepped(switcher.selected, step, switcher.windows.len());
+    }
+    emit_state(app);
+}
+
+// Brings the picked window forward. With the OSK open it also becomes the typing target.
+pub fn activate_selected(app: &AppHandle) {
+    let Some(switcher) = close(app) else {
+        return;
+    };
+    if let Some(window) = switcher.windows.get(switcher.selected) {
+        activate(app, window.handle);
+    }
+}
+
+pub fn activate(app: &AppHandle, handle: isize) -> bool {
+    {
+        let state_handle = app.state::<SharedAppState>();
+        let mut state = state_handle.lock().unwrap();
+        if state.osk_open {
+            state.target_hwnd = handle;
+        }
+    }
+    native_window::activate(handle)
+}
+
+// Null when the switcher is closed
+pub fn emit_state(app: &AppHandle) {
+    let payload = {
+        let state_handle = app.state::<SharedAppState>();
+        let state = state_handle.lock().unwrap();
+        state.switcher.as_ref().map(|switcher| {
+            serde_json::json!({
+                "windows": switcher.windows,
+                "selected": switcher.selected,
+            })
+        })
+    };
+    let _ = app.emit("osk:windows", payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(handle: isize) -> WindowInfo {
+        WindowInfo {
+            handle,
+            title: format!("window {}", handle),
+            process: None,
+        }
+    }
+
+    #[test]
+    fn opens_on_the_window_behind_the_foreground_one() {
+        let windows = [window(1), window(2), window(3)];
+        assert_eq!(first_selection(&windows, 1), 1);
+        // Focus is elsewhere (e.g. the desktop): the front window is the pick
+        assert_eq!(first_selection(&windows, 9), 0);
+        assert_eq!(first_selection(&[window(1)], 1), 0);
+        assert_eq!(first_selection(&[], 1), 0);
+    }
+
+    #[test]
+    fn stepping_wraps_around() {
+        assert_eq!(stepped(0, -1, 3), 2);
+        assert_eq!(stepped(2, 1, 3), 0);
+        assert_eq!(stepped(1, 4, 3), 2);
+    }
+}
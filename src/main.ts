@@ -1,7 +1,7 @@
 
 import Keyboard from 'simple-keyboard';
 import 'simple-keyboard/build/css/index.css';
-import { initNavigation, handleMove, handleSelect, getActiveKey } from './navigation';
+import { initNavigation, handleMove, handleSelect, getActiveKey, getActiveElement } from './navigation';
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { check } from '@tauri-apps/plugin-updater';
@@ -10,8 +10,8 @@ import { relaunch } from '@tauri-apps/plugin-process';
 const keyboard = new Keyboard({
   onChange: input => onChange(input),
   onKeyPress: button => onKeyPress(button),
-  onInit: () => { refreshTabIndex(); setupButtonListeners(); },
-  onRender: () => { refreshTabIndex(); setupButtonListeners(); },
+  onInit: () => { refreshTabIndex(); setupButtonListeners(); reportKeyGeometry(); },
+  onRender: () => { refreshTabIndex(); setupButtonListeners(); reportKeyGeometry(); },
   theme: "hg-theme-default myTheme1"
 });
 
@@ -21,6 +21,25 @@ function refreshTabIndex() {
   });
 }
 
+// Key centres for swipe typing, in the same pixel space as the `osk:swipe` pointer
+function reportKeyGeometry() {
+    const keys = Array.from(document.querySelectorAll('.hg-button')).flatMap(btn => {
+        const key = btn.getAttribute('data-skbtn');
+        const rect = btn.getBoundingClientRect();
+        if (!key || rect.width === 0) return [];
+        return [{
+            key,
+            x: rect.left + rect.width / 2,
+            y: rect.top + rect.height / 2,
+            width: rect.width,
+            height: rect.height,
+        }];
+    });
+    invoke('set_key_geometry', { keys }).catch(e => console.error("Failed to report key geometry", e));
+}
+
+window.addEventListener('resize', () => reportKeyGeometry());
+
 function setupButtonListeners() {
   const buttons = document.querySelectorAll('.hg-button');
   buttons.forEach(btn => {
@@ -119,6 +138,8 @@ function handleShift() {
 
   let currentLayout = keyboard.options.layoutName;
   let shiftToggle = currentLayout === "default" ? "shift" : "default";
+  // Numeric/symbol pages have no shifted layer
+  if (!keyboard.options.layout?.[shiftToggle]) return;
 
   keyboard.setOptions({
     layoutName: shiftToggle
@@ -145,7 +166,18 @@ let selectRepeatTimer: number | null = null;
 listen('osk:nav:select', (event: any) => {
     const { phase } = event.payload;
     handleSelect(phase);
-    
+
+    const word = getActiveElement()?.dataset.suggestion;
+    if (word) {
+        if (phase === 'down') acceptSuggestion(word);
+        return;
+    }
+    const stagingAction = getActiveElement()?.dataset.staging;
+    if (stagingAction) {
+        if (phase === 'down') runStagingAction(stagingAction);
+        return;
+    }
+
     const key = getActiveKey();
     if (!key) return;
     
@@ -167,10 +199,375 @@ listen('osk:nav:select', (event: any) => {
     }
 });
 
+// Fixed slots, so stepping onto a suggestion with the D-pad survives the list changing
+const SUGGESTION_SLOTS = 3;
+const suggestionEls: HTMLButtonElement[] = [];
+const suggestionsEl = document.querySelector(".suggestions") as HTMLDivElement | null;
+for (let i = 0; i < SUGGESTION_SLOTS && suggestionsEl; i++) {
+    const chipEl = document.createElement('button');
+    chipEl.className = 'nav-item invisible flex-1 rounded bg-gray-700 text-white';
+    chipEl.addEventListener('click', () => {
+        if (chipEl.dataset.suggestion) acceptSuggestion(chipEl.dataset.suggestion);
+    });
+    suggestionsEl.appendChild(chipEl);
+    suggestionEls.push(chipEl);
+}
+
+async function acceptSuggestion(word: string) {
+    try {
+        await invoke('accept_suggestion', { word });
+    } catch (e) {
+        console.error("Failed to accept suggestion", e);
+    }
+}
+
+listen('osk:suggestions', (event: any) => {
+    const { suggestions } = event.payload;
+    suggestionEls.forEach((chipEl, i) => {
+        const word: string | undefined = suggestions[i];
+        chipEl.textContent = word ?? '';
+        chipEl.classList.toggle('invisible', !word);
+        if (word) {
+            chipEl.dataset.suggestion = word;
+        } else {
+            delete chipEl.dataset.suggestion;
+        }
+    });
+});
+
+// Modes drawn by their own view in place of the keyboard
+const MODE_VIEWS: Record<string, string> = {
+    daisywheel: '.daisywheel',
+    split: '.split',
+    chord: '.chord',
+    t9: '.t9',
+};
+
+listen('osk:mode_changed', (event: any) => {
+    const mode: string = event.payload;
+    document.querySelector('.simple-keyboard')?.classList.toggle('hidden', mode in MODE_VIEWS);
+    Object.entries(MODE_VIEWS).forEach(([viewMode, selector]) => {
+        document.querySelector(selector)?.classList.toggle('hidden', viewMode !== mode);
+    });
+    if (mode !== 'swipe') document.querySelector('.swipe-pointer')?.classList.add('hidden');
+    // Hidden keys have no size, so measure again once the keyboard is back
+    reportKeyGeometry();
+});
+
+listen('osk:swipe', (event: any) => {
+    const pointerEl = document.querySelector(".swipe-pointer") as HTMLDivElement | null;
+    if (!pointerEl) return;
+    const { pointer, tracing } = event.payload;
+    pointerEl.classList.toggle('hidden', !pointer);
+    if (!pointer) return;
+    pointerEl.style.left = `${pointer.x}px`;
+    pointerEl.style.top = `${pointer.y}px`;
+    pointerEl.classList.toggle('bg-red-500', tracing);
+    pointerEl.classList.toggle('bg-blue-400', !tracing);
+});
+
+// Petals clockwise from the top, characters at the face buttons that type them
+listen('osk:daisywheel', (event: any) => {
+    const wheelEl = document.querySelector(".daisywheel") as HTMLDivElement | null;
+    if (!wheelEl) return;
+    wheelEl.replaceChildren();
+
+    const { petal, petals } = event.payload;
+    petals.forEach((chars: string, i: number) => {
+        const angle = (i / petals.length) * 2 * Math.PI;
+        const petalEl = document.createElement('div');
+        petalEl.className = `absolute -translate-x-1/2 -translate-y-1/2 grid grid-cols-3 w-16 h-16 rounded-full text-white text-sm place-items-center ${i === petal ? 'bg-blue-600' : 'bg-gray-700'}`;
+        petalEl.style.left = `${50 + 38 * Math.sin(angle)}%`;
+        petalEl.style.top = `${50 - 38 * Math.cos(angle)}%`;
+        // West, North, East, South on a 3x3 grid
+        const cells = ['', chars[1], '', chars[0], '', chars[2], '', chars[3], ''];
+        cells.forEach(c => {
+            const cellEl = document.createElement('span');
+            cellEl.textContent = c ?? '';
+            petalEl.appendChild(cellEl);
+        });
+        wheelEl.appendChild(petalEl);
+    });
+});
+
+// One block per stick; the key each stick points at is lit
+listen('osk:split', (event: any) => {
+    const splitEl = document.querySelector(".split") as HTMLDivElement | null;
+    if (!splitEl) return;
+    splitEl.replaceChildren();
+
+    const { halves, left, right } = event.payload;
+    [[halves.left, left], [halves.right, right]].forEach(([rows, pointed]) => {
+        const halfEl = document.createElement('div');
+        halfEl.className = 'flex flex-col gap-1';
+        rows.forEach((keys: string[], row: number) => {
+            const rowEl = document.createElement('div');
+            rowEl.className = 'flex gap-1';
+            keys.forEach((key, col) => {
+                const keyEl = document.createElement('div');
+                const lit = pointed && pointed.row === row && pointed.col === col;
+                keyEl.textContent = key;
+                keyEl.className = `w-10 h-10 flex items-center justify-center rounded text-white ${lit ? 'bg-blue-600' : 'bg-gray-700'}`;
+                rowEl.appendChild(keyEl);
+            });
+            halfEl.appendChild(rowEl);
+        });
+        splitEl.appendChild(halfEl);
+    });
+});
+
+const BUTTON_LABELS: Record<string, string> = {
+    south: 'A', east: 'B', west: 'X', north: 'Y', lb: 'LB', rb: 'RB',
+};
+
+let chordCheatSheet: any[] = [];
+let chordState: any = { chord: [], shift: false };
+
+function chordOutputLabel(output: any): string {
+    switch (output.type) {
+        case 'text': return chordState.shift ? output.text.charAt(0).toUpperCase() + output.text.slice(1) : output.text;
+        case 'key': return output.key;
+        case 'shift': return 'Shift';
+        default: return 'Close';
+    }
+}
+
+// The cheat sheet, lighting every chord the buttons pressed so far can still become
+function renderChord() {
+    const chordEl = document.querySelector(".chord") as HTMLDivElement | null;
+    if (!chordEl) return;
+    chordEl.replaceChildren();
+
+    const pressed: string[] = chordState.chord;
+    chordCheatSheet.forEach(entry => {
+        const reachable = pressed.length > 0 && pressed.every(b => entry.buttons.includes(b));
+        const entryEl = document.createElement('div');
+        entryEl.className = `flex justify-between px-2 py-1 rounded text-white ${reachable ? 'bg-blue-600' : 'bg-gray-700'}`;
+        const buttonsEl = document.createElement('span');
+        buttonsEl.textContent = entry.buttons.map((b: string) => BUTTON_LABELS[b] ?? b).join('+');
+        const outputEl = document.createElement('span');
+        outputEl.textContent = chordOutputLabel(entry.output);
+        entryEl.append(buttonsEl, outputEl);
+        chordEl.appendChild(entryEl);
+    });
+}
+
+listen('osk:chord:cheat_sheet', (event: any) => {
+    chordCheatSheet = event.payload;
+    renderChord();
+});
+
+listen('osk:chord', (event: any) => {
+    chordState = event.payload;
+    renderChord();
+});
+
+// Candidates on top, then the keypad with the focused key lit
+listen('osk:t9', (event: any) => {
+    const t9El = document.querySelector(".t9") as HTMLDivElement | null;
+    if (!t9El) return;
+    t9El.replaceChildren();
+
+    const { cells, focus, pending, candidates, candidate, shift } = event.payload;
+    const candidatesEl = document.createElement('div');
+    candidatesEl.className = 'flex gap-1 h-8 text-white';
+    candidates.forEach((word: string, i: number) => {
+        const wordEl = document.createElement('span');
+        wordEl.textContent = word;
+        wordEl.className = `px-2 py-1 rounded ${i === candidate ? 'bg-blue-600' : 'bg-gray-700'}`;
+        candidatesEl.appendChild(wordEl);
+    });
+    t9El.appendChild(candidatesEl);
+
+    const padEl = document.createElement('div');
+    padEl.className = 'grid grid-cols-3 gap-1';
+    cells.forEach((row: string[], r: number) => {
+        row.forEach((letters, c) => {
+            const keyEl = document.createElement('div');
+            const focused = focus.row === r && focus.col === c;
+            keyEl.className = `h-16 flex items-center justify-center rounded text-white text-lg ${letters ? (focused ? 'bg-blue-600' : 'bg-gray-700') : 'invisible'}`;
+            const shown = letters === ' ' ? '␣' : letters;
+            keyEl.textContent = shift ? shown.toUpperCase() : shown;
+            if (pending && pending.row === r && pending.col === c && pending.char) {
+                keyEl.textContent = shift ? pending.char.toUpperCase() : pending.char;
+                keyEl.classList.add('text-3xl');
+            }
+            padEl.appendChild(keyEl);
+        });
+    });
+    t9El.appendChild(padEl);
+});
+
+// Variants of a held key; the D-pad moves the highlight and releasing the key types it
+listen('osk:alternates', (event: any) => {
+    const alternatesEl = document.querySelector(".alternates") as HTMLDivElement | null;
+    if (!alternatesEl) return;
+    alternatesEl.classList.toggle('hidden', !event.payload);
+    alternatesEl.replaceChildren();
+    if (!event.payload) return;
+
+    const { alternates, selected } = event.payload;
+    alternates.forEach((variant: string, i: number) => {
+        const variantEl = document.createElement('div');
+        variantEl.textContent = variant;
+        variantEl.className = `w-10 h-10 flex items-center justify-center rounded text-white text-xl ${i === selected ? 'bg-blue-600' : 'bg-gray-700'}`;
+        alternatesEl.appendChild(variantEl);
+    });
+});
+
+// Keys waiting to combine: a Compose sequence or a dead key
+listen('osk:compose', (event: any) => {
+    const composeEl = document.querySelector(".compose") as HTMLDivElement | null;
+    if (!composeEl) return;
+    composeEl.classList.toggle('hidden', !event.payload);
+    if (!event.payload) return;
+    const { kind, keys } = event.payload;
+    composeEl.textContent = kind === 'compose' ? `Compose ${keys}` : keys;
+});
+
+// Reading being composed and its conversions, over the suggestions; bumpers pick
+listen('osk:ime', (event: any) => {
+    const imeEl = document.querySelector(".ime") as HTMLDivElement | null;
+    if (!imeEl) return;
+    imeEl.classList.toggle('hidden', !event.payload);
+    imeEl.replaceChildren();
+    if (!event.payload) return;
+
+    const { composition, candidates, selected } = event.payload;
+    const readingEl = document.createElement('span');
+    readingEl.textContent = composition;
+    readingEl.className = 'px-2 underline';
+    imeEl.appendChild(readingEl);
+    candidates.forEach((word: string, i: number) => {
+        const wordEl = document.createElement('span');
+        wordEl.textContent = word;
+        wordEl.className = `px-2 py-1 rounded ${i === selected ? 'bg-blue-600' : 'bg-gray-700'}`;
+        imeEl.appendChild(wordEl);
+    });
+});
+
 listen('osk:nav:shift', () => {
     handleShift();
 });
 
+async function runStagingAction(action: string) {
+    try {
+        await invoke('staging_action', { action });
+    } catch (e) {
+        console.error("Failed to run staging action", e);
+    }
+}
+
+document.querySelectorAll<HTMLButtonElement>('[data-staging]').forEach(btn => {
+    btn.addEventListener('click', () => runStagingAction(btn.dataset.staging!));
+});
+
+listen('osk:staging', (event: any) => {
+    const { enabled, text, cursor } = event.payload;
+    document.querySelector('.staging')?.classList.toggle('hidden', !enabled);
+    const inputEl = document.querySelector(".input") as HTMLInputElement | null;
+    if (!inputEl) return;
+    inputEl.value = text;
+    inputEl.setSelectionRange(cursor, cursor);
+});
+
+// Slots laid out clockwise from the top, like the stick sectors that pick them
+listen('osk:radial', (event: any) => {
+    const radialEl = document.querySelector(".radial") as HTMLDivElement | null;
+    if (!radialEl) return;
+    radialEl.classList.toggle('hidden', !event.payload);
+    radialEl.replaceChildren();
+    if (!event.payload) return;
+
+    const { slots, selected } = event.payload;
+    slots.forEach((label: string, i: number) => {
+        const angle = (i / slots.length) * 2 * Math.PI;
+        const slotEl = document.createElement('div');
+        slotEl.textContent = label;
+        slotEl.className = `absolute -translate-x-1/2 -translate-y-1/2 px-3 py-2 rounded text-white ${i === selected ? 'bg-blue-600' : 'bg-gray-700'}`;
+        slotEl.style.left = `${50 + 35 * Math.sin(angle)}%`;
+        slotEl.style.top = `${50 - 35 * Math.cos(angle)}%`;
+        radialEl.appendChild(slotEl);
+    });
+});
+
+listen('osk:windows', (event: any) => {
+    const switcherEl = document.querySelector(".switcher") as HTMLDivElement | null;
+    if (!switcherEl) return;
+    switcherEl.classList.toggle('hidden', !event.payload);
+    switcherEl.replaceChildren();
+    if (!event.payload) return;
+
+    const { windows, selected } = event.payload;
+    windows.forEach((win: any, i: number) => {
+        const rowEl = document.createElement('div');
+        rowEl.textContent = win.process ? `${win.title} — ${win.process}` : win.title;
+        rowEl.className = `px-3 py-2 rounded text-white truncate ${i === selected ? 'bg-blue-600' : ''}`;
+        switcherEl.appendChild(rowEl);
+        if (i === selected) rowEl.scrollIntoView({ block: 'nearest' });
+    });
+});
+
+listen('osk:clipboard', (event: any) => {
+    const clipboardEl = document.querySelector(".clipboard") as HTMLDivElement | null;
+    if (!clipboardEl) return;
+    clipboardEl.classList.toggle('hidden', !event.payload);
+    clipboardEl.replaceChildren();
+    if (!event.payload) return;
+
+    const hintEl = document.createElement('div');
+    hintEl.textContent = 'A paste · Y pin · X delete · B close';
+    hintEl.className = 'text-gray-400 text-sm mb-2';
+    clipboardEl.appendChild(hintEl);
+
+    const { clips, selected } = event.payload;
+    if (clips.length === 0) {
+        const emptyEl = document.createElement('div');
+        emptyEl.textContent = 'Nothing copied yet';
+        emptyEl.className = 'px-3 py-2 text-gray-400';
+        clipboardEl.appendChild(emptyEl);
+    }
+    clips.forEach((clip: any, i: number) => {
+        const rowEl = document.createElement('div');
+        rowEl.textContent = clip.pinned ? `📌 ${clip.text}` : clip.text;
+        rowEl.className = `px-3 py-2 rounded text-white truncate ${i === selected ? 'bg-blue-600' : ''}`;
+        clipboardEl.appendChild(rowEl);
+        if (i === selected) rowEl.scrollIntoView({ block: 'nearest' });
+    });
+});
+
+listen('osk:confirm', (event: any) => {
+    const confirmEl = document.querySelector(".confirm") as HTMLDivElement | null;
+    if (!confirmEl) return;
+    confirmEl.classList.toggle('hidden', !event.payload);
+    confirmEl.textContent = event.payload ?? '';
+});
+
+const MEDIA_LABELS: Record<string, string> = {
+    playpause: 'Play / Pause',
+    mediastop: 'Stop',
+    nexttrack: 'Next track',
+    prevtrack: 'Previous track',
+    mute: 'Mute',
+    volumeup: 'Volume +',
+    volumedown: 'Volume −',
+};
+
+listen('osk:media', (event: any) => {
+    const mediaEl = document.querySelector(".media") as HTMLDivElement | null;
+    if (!mediaEl) return;
+    mediaEl.classList.toggle('hidden', !event.payload);
+    const key = event.payload?.key;
+    mediaEl.textContent = key ? (MEDIA_LABELS[key] ?? key) : 'Media';
+});
+
+listen('osk:layout', (event: any) => {
+    keyboard.setOptions({
+        layout: event.payload.layout,
+        layoutName: 'default'
+    });
+});
+
 async function checkForAppUpdates() {
     try {
         const update = await check();
@@ -2,6 +2,9 @@
 let activeElement: HTMLElement | null = null;
 let moveInterval: number | null = null;
 
+// Keyboard keys, plus anything else on the OSK that can be stepped to, like suggestions
+const NAV_SELECTOR = '.hg-button, .nav-item:not(.invisible)';
+
 export function initNavigation() {
     // Initial selection
     setTimeout(() => {
@@ -49,7 +52,7 @@ function move(dx: number, dy: number) {
         if (!activeElement) return;
     }
 
-    const all = Array.from(document.querySelectorAll('.hg-button')) as HTMLElement[];
+    const all = Array.from(document.querySelectorAll(NAV_SELECTOR)) as HTMLElement[];
     const currentRect = activeElement!.getBoundingClientRect();
     const currentCenter = {
         x: currentRect.left + currentRect.width / 2,
@@ -63,6 +66,8 @@ function move(dx: number, dy: number) {
         if (candidate === activeElement) continue;
 
         const rect = candidate.getBoundingClientRect();
+        // Inside a hidden container
+        if (rect.width === 0) continue;
         const center = {
             x: rect.left + rect.width / 2,
             y: rect.top + rect.height / 2
@@ -131,3 +136,7 @@ export function handleSelect(phase: 'down' | 'repeat' | 'up') {
 export function getActiveKey(): string | null {
     return activeElement ? activeElement.getAttribute('data-skbtn') : null;
 }
+
+export function getActiveElement(): HTMLElement | null {
+    return activeElement;
+}
@@ -0,0 +1,29 @@
+import { listen } from "@tauri-apps/api/event";
+
+// The backend sends physical pixels relative to the monitor the overlay covers
+listen('osk:grid_jump', (event: any) => {
+    const gridEl = document.querySelector(".grid-jump") as HTMLDivElement | null;
+    if (!gridEl) return;
+    gridEl.classList.toggle('hidden', !event.payload);
+    gridEl.replaceChildren();
+    if (!event.payload) return;
+
+    const { region, cell } = event.payload;
+    const [x, y, width, height] = region.map((v: number) => v / window.devicePixelRatio);
+    Object.assign(gridEl.style, { left: `${x}px`, top: `${y}px`, width: `${width}px`, height: `${height}px` });
+
+    for (let row = 0; row < 3; row++) {
+        for (let col = 0; col < 3; col++) {
+            const cellEl = document.createElement('div');
+            const selected = col === cell[0] && row === cell[1];
+            cellEl.className = `absolute border-2 border-blue-400 ${selected ? 'bg-blue-500/40' : 'bg-gray-900/10'}`;
+            Object.assign(cellEl.style, {
+                left: `${(col * 100) / 3}%`,
+                top: `${(row * 100) / 3}%`,
+                width: `${100 / 3}%`,
+                height: `${100 / 3}%`,
+            });
+            gridEl.appendChild(cellEl);
+        }
+    }
+});
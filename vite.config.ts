@@ -14,6 +14,7 @@ export default defineConfig(async () => ({
       input: {
         main: resolve(__dirname, 'src/index.html'),
         settings: resolve(__dirname, 'src/settings.html'),
+        overlay: resolve(__dirname, 'src/overlay.html'),
       },
     },
   },